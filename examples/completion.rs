@@ -0,0 +1,78 @@
+use qargparser as arg;
+
+#[derive(Default, Debug)]
+struct MyContext {
+  do_help: bool,
+  completion: Option<arg::Shell>,
+  verbosity: u8,
+  file: String
+}
+
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+  let help_spec = arg::Builder::new()
+    .sopt('h')
+    .lopt("help")
+    .exit(true)
+    .help(&["Show this help."])
+    .build(|_spec, ctx: &mut MyContext, _args| {
+      ctx.do_help = true;
+    });
+  let completion_spec = arg::Builder::new()
+    .lopt("completion")
+    .exit(true)
+    .nargs(arg::Nargs::Count(1), &["SHELL"])
+    .choices(&["bash", "zsh", "fish"])
+    .help(&["Output a completion script for SHELL."])
+    .build(|_spec, ctx: &mut MyContext, args| {
+      ctx.completion = match args[0].as_str() {
+        "bash" => Some(arg::Shell::Bash),
+        "zsh" => Some(arg::Shell::Zsh),
+        _ => Some(arg::Shell::Fish)
+      };
+    });
+  let verbose_spec = arg::Builder::new()
+    .sopt('v')
+    .lopt("verbose")
+    .help(&["Increase verbosity level."])
+    .build(|_spec, ctx: &mut MyContext, _args| {
+      ctx.verbosity += 1;
+    });
+  let file_spec = arg::Builder::new()
+    .name("file")
+    .nargs(arg::Nargs::Count(1), &["FILE"])
+    .help(&["File to process."])
+    .build(|_spec, ctx: &mut MyContext, args| {
+      ctx.file = args[0].clone();
+    });
+
+  let ctx = MyContext {
+    ..Default::default()
+  };
+  let mut prsr = arg::Parser::from_env(ctx);
+
+  prsr.add(help_spec)?;
+  prsr.add(completion_spec)?;
+  prsr.add(verbose_spec)?;
+  prsr.add(file_spec)?;
+
+  prsr.parse()?;
+
+  if prsr.get_ctx().do_help {
+    prsr.usage(&mut std::io::stdout());
+    std::process::exit(0);
+  }
+
+  if let Some(shell) = prsr.get_ctx().completion {
+    prsr.completion(shell, &mut std::io::stdout());
+    std::process::exit(0);
+  }
+
+  let ctx = prsr.into_ctx();
+
+  println!("{:?}", &ctx);
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
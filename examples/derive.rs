@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use qargparser::QArgs;
+
+#[derive(Default, Debug, QArgs)]
+struct MyContext {
+  /// Show this help.
+  #[qarg(sopt = 'h', lopt = "help", exit)]
+  do_help: bool,
+
+  /// Output tool version and exit.
+  #[qarg(sopt = 'V', lopt = "version", exit)]
+  do_version: bool,
+
+  /// Port to listen on.
+  #[qarg(sopt = 'p', lopt, meta = "PORT")]
+  port: Option<u16>,
+
+  /// Set a parameter.
+  #[qarg(sopt = 'P', lopt = "param", meta = "KEY", meta = "VALUE")]
+  params: Vec<(String, String)>,
+
+  /// Input file.
+  #[qarg(meta = "FILE", required)]
+  fname: PathBuf
+}
+
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = MyContext::from_env()?;
+
+  prsr.parse()?;
+
+  if prsr.get_ctx().do_help {
+    prsr.usage(&mut std::io::stdout());
+    std::process::exit(0);
+  }
+
+  if prsr.get_ctx().do_version {
+    const VERSION: &str = env!("CARGO_PKG_VERSION");
+    println!("derive {}", VERSION);
+    std::process::exit(0);
+  }
+
+  let ctx = prsr.into_ctx();
+
+  println!("{:?}", &ctx);
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
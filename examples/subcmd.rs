@@ -0,0 +1,99 @@
+use qargparser as arg;
+
+#[derive(Default, Debug)]
+struct MyContext {
+  do_help: bool,
+  verbosity: u8,
+  force: bool,
+  src: String,
+  name: String
+}
+
+
+fn mkhelp() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('h')
+    .lopt("help")
+    .exit(true)
+    .help(&["Show this help."])
+    .build(|_spec, ctx: &mut MyContext, _args| {
+      ctx.do_help = true;
+    })
+}
+
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+  let verbose_spec = arg::Builder::new()
+    .sopt('v')
+    .lopt("verbose")
+    .global(true)
+    .help(&["Increase verbosity level.  May be used before or after the \
+             command."])
+    .build(|_spec, ctx: &mut MyContext, _args| {
+      ctx.verbosity += 1;
+    });
+
+  let mut clone = arg::Parser::new_subcmd();
+  clone.add(mkhelp())?;
+  clone.add(
+    arg::Builder::new()
+      .sopt('f')
+      .lopt("force")
+      .help(&["Overwrite existing files."])
+      .build(|_spec, ctx: &mut MyContext, _args| {
+        ctx.force = true;
+      })
+  )?;
+  clone.add(
+    arg::Builder::new()
+      .name("src")
+      .required(true)
+      .nargs(arg::Nargs::Count(1), &["SRC"])
+      .help(&["The repository to clone."])
+      .build(|_spec, ctx: &mut MyContext, args| {
+        ctx.src = args[0].clone();
+      })
+  )?;
+
+  let mut init = arg::Parser::new_subcmd();
+  init.add(mkhelp())?;
+  init.add(
+    arg::Builder::new()
+      .name("name")
+      .nargs(arg::Nargs::Count(1), &["NAME"])
+      .help(&["Name of the new repository."])
+      .build(|_spec, ctx: &mut MyContext, args| {
+        ctx.name = args[0].clone();
+      })
+  )?;
+
+  let ctx = MyContext {
+    ..Default::default()
+  };
+  let mut prsr = arg::Parser::from_env(ctx);
+
+  prsr.add(mkhelp())?;
+  prsr.add(verbose_spec)?;
+  prsr.add_subcmd("clone", &["Clone a repository."], clone)?;
+  prsr.add_subcmd("init", &["Create an empty repository."], init)?;
+
+  prsr.parse()?;
+
+  if prsr.get_ctx().do_help {
+    match prsr.get_subcmd_parser() {
+      Some(sub) => sub.usage(&mut std::io::stdout()),
+      None => prsr.usage(&mut std::io::stdout())
+    }
+    std::process::exit(0);
+  }
+
+  println!("command: {:?}", prsr.get_subcmd());
+
+  let ctx = prsr.into_ctx();
+
+  println!("{:?}", &ctx);
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,345 @@
+//! Derive macro for qargparser.
+//!
+//! This crate is re-exported by qargparser when its `derive` feature is
+//! enabled; see `qargparser::QArgs` for documentation.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+  parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Field,
+  Fields, GenericArgument, Lit, LitChar, LitInt, LitStr, PathArguments, Type
+};
+
+
+#[proc_macro_derive(QArgs, attributes(qarg))]
+pub fn derive_qargs(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  match expand(&input) {
+    Ok(ts) => ts.into(),
+    Err(e) => e.to_compile_error().into()
+  }
+}
+
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+  let fields = match &input.data {
+    Data::Struct(s) => match &s.fields {
+      Fields::Named(f) => &f.named,
+      _ => {
+        return Err(Error::new_spanned(
+          input,
+          "QArgs can only be derived for structs with named fields"
+        ))
+      }
+    },
+    _ => {
+      return Err(Error::new_spanned(
+        input,
+        "QArgs can only be derived for structs"
+      ))
+    }
+  };
+
+  let mut adds = Vec::new();
+  for field in fields {
+    if field.attrs.iter().any(|a| a.path().is_ident("qarg")) {
+      adds.push(expand_field(field)?);
+    }
+  }
+
+  let ident = &input.ident;
+  let (impl_generics, ty_generics, where_clause) =
+    input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics ::qargparser::QArgs for #ident #ty_generics
+    #where_clause
+    {
+      fn add_specs(
+        prsr: &mut ::qargparser::Parser<Self>
+      ) -> ::std::result::Result<(), ::qargparser::ErrKind<Self>> {
+        #(#adds)*
+        ::std::result::Result::Ok(())
+      }
+    }
+  })
+}
+
+
+/// The contents of a field's `#[qarg(...)]` attributes.
+#[derive(Default)]
+struct Attrs {
+  sopt: Option<LitChar>,
+  lopt: Option<String>,
+  name: Option<String>,
+  help: Vec<String>,
+  nargs: Option<TokenStream2>,
+  /// Whether `nargs` lets the spec be matched without arguments.
+  nargs_opt: bool,
+  meta: Vec<String>,
+  required: bool,
+  hidden: bool,
+  exit: bool
+}
+
+/// How a field stores its arguments.
+enum Kind<'a> {
+  /// `bool`; set if the spec is matched.
+  Flag,
+
+  /// `Option<T>`; set to the last argument(s).
+  Option(&'a Type),
+
+  /// `Vec<T>`; extended with each match's argument(s).
+  Vec(&'a Type),
+
+  /// Any other type; set to the last argument(s).
+  Value(&'a Type)
+}
+
+
+fn expand_field(field: &Field) -> Result<TokenStream2, Error> {
+  let ident = field.ident.as_ref().unwrap();
+  let fname = ident.to_string();
+  let attrs = parse_attrs(field, &fname)?;
+  let kind = get_kind(&field.ty);
+  let is_pos = attrs.sopt.is_none() && attrs.lopt.is_none();
+
+  let mut bldr = Vec::new();
+  if let Some(c) = &attrs.sopt {
+    bldr.push(quote! { .sopt(#c) });
+  }
+  if let Some(o) = &attrs.lopt {
+    bldr.push(quote! { .lopt(#o) });
+  }
+  let name = attrs.name.clone().unwrap_or_else(|| fname.clone());
+  bldr.push(quote! { .name(#name) });
+
+  if is_pos && matches!(kind, Kind::Flag) {
+    return Err(Error::new_spanned(
+      field,
+      "bool fields must be options; add `sopt` or `lopt`"
+    ));
+  }
+  if attrs.nargs_opt && !matches!(kind, Kind::Option(_) | Kind::Vec(_)) {
+    return Err(Error::new_spanned(
+      field,
+      "nargs \"?\" and \"*\" require an Option or Vec field"
+    ));
+  }
+
+  let (nargs, count) = match (&attrs.nargs, &kind) {
+    (Some(_), Kind::Flag) => {
+      return Err(Error::new_spanned(
+        field,
+        "bool fields can't take arguments"
+      ));
+    }
+    (Some(nargs), _) => (Some(nargs.clone()), None),
+    (None, Kind::Flag) => (None, None),
+    (None, Kind::Vec(_)) if is_pos => {
+      if attrs.required {
+        (Some(quote! { ::qargparser::Nargs::OneOrMore }), None)
+      } else {
+        (Some(quote! { ::qargparser::Nargs::ZeroOrMore }), None)
+      }
+    }
+    (None, Kind::Vec(ty))
+    | (None, Kind::Option(ty))
+    | (None, Kind::Value(ty)) => {
+      let n = match ty {
+        Type::Tuple(t) => t.elems.len(),
+        _ => 1
+      };
+      (Some(quote! { ::qargparser::Nargs::Count(#n) }), Some(n))
+    }
+  };
+  if let Some(nargs) = nargs {
+    let meta = if attrs.meta.is_empty() {
+      vec![fname.to_uppercase(); count.unwrap_or(1)]
+    } else {
+      attrs.meta.clone()
+    };
+    bldr.push(quote! { .nargs(#nargs, &[#(#meta),*]) });
+  }
+
+  let required = attrs.required;
+  let hidden = attrs.hidden;
+  let exit = attrs.exit;
+  bldr.push(quote! {
+    .required(#required)
+    .hidden(#hidden)
+    .exit(#exit)
+  });
+
+  let help = if attrs.help.is_empty() {
+    get_doc(&field.attrs)
+  } else {
+    attrs.help.clone()
+  };
+  if !help.is_empty() {
+    bldr.push(quote! { .help(&[#(#help),*]) });
+  }
+
+  let (ty, assign) = match kind {
+    Kind::Flag => (quote! { () }, quote! { ctx.#ident = true }),
+    // The option may be matched without a value, which sets the field to
+    // `None`.
+    Kind::Option(ty) if attrs.nargs_opt => {
+      (quote! { ::std::option::Option<#ty> }, quote! { ctx.#ident = v })
+    }
+    Kind::Option(ty) => {
+      (quote! { #ty }, quote! { ctx.#ident = ::std::option::Option::Some(v) })
+    }
+    // Options which take one value per match, like `-I a -I b`, push each
+    // value, which lets them take tuples.
+    Kind::Vec(ty) if attrs.nargs.is_none() && !is_pos => {
+      (quote! { #ty }, quote! { ctx.#ident.push(v) })
+    }
+    Kind::Vec(ty) => {
+      (quote! { ::std::vec::Vec<#ty> }, quote! { ctx.#ident.extend(v) })
+    }
+    Kind::Value(ty) => (quote! { #ty }, quote! { ctx.#ident = v })
+  };
+
+  Ok(quote! {
+    prsr.add(
+      ::qargparser::Builder::new()
+        #(#bldr)*
+        .build_typed(|_spec, ctx: &mut Self, v: #ty| {
+          #assign;
+          ::std::result::Result::Ok(())
+        })
+    )?;
+  })
+}
+
+
+fn parse_attrs(field: &Field, fname: &str) -> Result<Attrs, Error> {
+  let mut attrs = Attrs::default();
+
+  for attr in field.attrs.iter().filter(|a| a.path().is_ident("qarg")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("sopt") {
+        attrs.sopt = Some(meta.value()?.parse::<LitChar>()?);
+      } else if meta.path.is_ident("lopt") {
+        // A bare `lopt` derives the long option from the field name.
+        attrs.lopt = if meta.input.peek(syn::Token![=]) {
+          Some(meta.value()?.parse::<LitStr>()?.value())
+        } else {
+          Some(fname.replace('_', "-"))
+        };
+      } else if meta.path.is_ident("name") {
+        attrs.name = Some(meta.value()?.parse::<LitStr>()?.value());
+      } else if meta.path.is_ident("help") {
+        attrs.help.push(meta.value()?.parse::<LitStr>()?.value());
+      } else if meta.path.is_ident("meta") {
+        attrs.meta.push(meta.value()?.parse::<LitStr>()?.value());
+      } else if meta.path.is_ident("nargs") {
+        let lit = meta.value()?.parse::<Lit>()?;
+        attrs.nargs_opt = matches!(
+          &lit,
+          Lit::Str(s) if s.value() == "?" || s.value() == "*"
+        );
+        attrs.nargs = Some(parse_nargs(&lit)?);
+      } else if meta.path.is_ident("required") {
+        attrs.required = true;
+      } else if meta.path.is_ident("hidden") {
+        attrs.hidden = true;
+      } else if meta.path.is_ident("exit") {
+        attrs.exit = true;
+      } else {
+        return Err(meta.error("unknown qarg attribute"));
+      }
+      Ok(())
+    })?;
+  }
+
+  Ok(attrs)
+}
+
+
+/// Translate `nargs = 2`, `nargs = "?"` etc to a `Nargs` value.
+fn parse_nargs(lit: &Lit) -> Result<TokenStream2, Error> {
+  match lit {
+    Lit::Int(n) => {
+      let n = n.base10_parse::<usize>()?;
+      let n = LitInt::new(&n.to_string(), Span::call_site());
+      Ok(quote! { ::qargparser::Nargs::Count(#n) })
+    }
+    Lit::Str(s) => match s.value().as_str() {
+      "?" => Ok(quote! { ::qargparser::Nargs::Optional }),
+      "*" => Ok(quote! { ::qargparser::Nargs::ZeroOrMore }),
+      "+" => Ok(quote! { ::qargparser::Nargs::OneOrMore }),
+      "..." => Ok(quote! { ::qargparser::Nargs::Remainder }),
+      _ => Err(Error::new_spanned(
+        s,
+        "expected a count or one of \"?\", \"*\", \"+\" and \"...\""
+      ))
+    },
+    _ => Err(Error::new_spanned(lit, "expected a count or a string"))
+  }
+}
+
+
+fn get_kind(ty: &Type) -> Kind<'_> {
+  if let Type::Path(p) = ty {
+    if p.qself.is_none() {
+      let seg = p.path.segments.last().unwrap();
+      if seg.ident == "bool" {
+        return Kind::Flag;
+      }
+      if let PathArguments::AngleBracketed(args) = &seg.arguments {
+        if let Some(GenericArgument::Type(inner)) = args.args.first() {
+          if seg.ident == "Option" {
+            return Kind::Option(inner);
+          }
+          if seg.ident == "Vec" {
+            return Kind::Vec(inner);
+          }
+        }
+      }
+    }
+  }
+  Kind::Value(ty)
+}
+
+
+/// Turn doc comments into help paragraphs.  Paragraphs are separated by
+/// empty lines.
+fn get_doc(attrs: &[Attribute]) -> Vec<String> {
+  let mut paras = Vec::new();
+  let mut cur = String::new();
+
+  for attr in attrs.iter().filter(|a| a.path().is_ident("doc")) {
+    let line = match &attr.meta.require_name_value() {
+      Ok(nv) => match &nv.value {
+        Expr::Lit(e) => match &e.lit {
+          Lit::Str(s) => s.value(),
+          _ => continue
+        },
+        _ => continue
+      },
+      Err(_) => continue
+    };
+    let line = line.trim();
+    if line.is_empty() {
+      if !cur.is_empty() {
+        paras.push(std::mem::take(&mut cur));
+      }
+      continue;
+    }
+    if !cur.is_empty() {
+      cur.push(' ');
+    }
+    cur.push_str(line);
+  }
+  if !cur.is_empty() {
+    paras.push(cur);
+  }
+
+  paras
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,480 @@
+//! Shell completion script generation.
+
+use std::path::Path;
+
+use crate::parser::{Parser, SpecRc};
+use crate::spec::{Nargs, Spec};
+
+
+/// Shells for which [`Parser::completion()`](Parser::completion) can generate
+/// completion scripts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shell {
+  Bash,
+  Zsh,
+  Fish
+}
+
+
+/// A (sub-)command and the visible options it accepts.
+struct Cmd<'a, C> {
+  /// Sub-command names leading to this command.  Empty for the top level.
+  path: Vec<&'a str>,
+  prsr: &'a Parser<C>,
+  /// Visible options, including global options inherited from parents.
+  opts: Vec<SpecRc<C>>
+}
+
+
+/// Collect `prsr` and all its sub-commands, depth first.
+fn collect<'a, C>(
+  prsr: &'a Parser<C>,
+  path: Vec<&'a str>,
+  inherited: &[SpecRc<C>],
+  cmds: &mut Vec<Cmd<'a, C>>
+) {
+  let mut opts: Vec<SpecRc<C>> = prsr
+    .get_opts()
+    .into_iter()
+    .filter(|spec| !spec.borrow().is_hidden())
+    .collect();
+
+  // Options defined by the sub-command itself shadow inherited ones.
+  for spec in inherited {
+    let names = spec.borrow().get_opt_strs();
+    let shadowed = opts.iter().any(|o| {
+      o.borrow().get_opt_strs().iter().any(|n| names.contains(n))
+    });
+    if !shadowed {
+      opts.push(spec.clone());
+    }
+  }
+
+  let globals: Vec<SpecRc<C>> = opts
+    .iter()
+    .filter(|spec| spec.borrow().is_global())
+    .cloned()
+    .collect();
+
+  cmds.push(Cmd { path: path.clone(), prsr, opts });
+
+  for sub in &prsr.subcmds {
+    let mut subpath = path.clone();
+    subpath.push(&sub.name);
+    collect(&sub.prsr, subpath, &globals, cmds);
+  }
+}
+
+
+/// Get the first paragraph of a help text as a single line.
+fn get_desc(help: &[String]) -> String {
+  match help.first() {
+    Some(p) => p.split_whitespace().collect::<Vec<&str>>().join(" "),
+    None => String::new()
+  }
+}
+
+/// Turn a command path into something which can be used in a shell function
+/// name.
+fn ident(name: &str, path: &[&str]) -> String {
+  let mut ret = name.to_string();
+  for p in path {
+    ret.push_str("__");
+    ret.push_str(p);
+  }
+  ret
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+    .collect()
+}
+
+/// Quote a string using single quotes, for bash and zsh.
+fn sh_quote(s: &str) -> String {
+  format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Quote a string using single quotes, for fish.
+fn fish_quote(s: &str) -> String {
+  format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+
+impl<C> Parser<C> {
+  /// Write a completion script for `shell` to `out`.
+  ///
+  /// The command name is taken from the base name of the parser's `argv0`.
+  /// Hidden specs are left out, and descriptions (where the shell supports
+  /// them) are taken from the first paragraph of each spec's help text.
+  pub fn completion(&self, shell: Shell, out: &mut dyn std::io::Write) {
+    let name = match Path::new(&self.argv0).file_name() {
+      Some(name) => name.to_string_lossy().to_string(),
+      None => self.argv0.clone()
+    };
+
+    let mut cmds = Vec::new();
+    collect(self, Vec::new(), &[], &mut cmds);
+
+    let script = match shell {
+      Shell::Bash => gen_bash(&name, &cmds),
+      Shell::Zsh => gen_zsh(&name, &cmds),
+      Shell::Fish => gen_fish(&name, &cmds)
+    };
+
+    out
+      .write_all(script.as_bytes())
+      .expect("Unable to write output.");
+  }
+}
+
+
+fn gen_bash<C>(name: &str, cmds: &[Cmd<C>]) -> String {
+  let func = format!("_{}", ident(name, &[]));
+  let mut s = String::new();
+
+  s.push_str(&format!("{}() {{\n", func));
+  s.push_str("  local cur prev cmd opts argopts cmds posargs i\n");
+  s.push_str("  cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+  s.push_str("  prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+  s.push_str(&format!("  cmd={}\n\n", sh_quote(name)));
+
+  // Walk the words on the command line to find the active sub-command.
+  s.push_str("  for ((i = 1; i < COMP_CWORD; i++)); do\n");
+  s.push_str("    case \"${cmd},${COMP_WORDS[i]}\" in\n");
+  for cmd in cmds {
+    if let Some((last, parent)) = cmd.path.split_last() {
+      let mut from = vec![name];
+      from.extend(parent);
+      let mut to = vec![name];
+      to.extend(&cmd.path);
+      s.push_str(&format!(
+        "      {}) cmd={} ;;\n",
+        sh_quote(&format!("{},{}", from.join(" "), last)),
+        sh_quote(&to.join(" "))
+      ));
+    }
+  }
+  s.push_str("    esac\n");
+  s.push_str("  done\n\n");
+
+  s.push_str("  case \"${cmd}\" in\n");
+  for cmd in cmds {
+    let mut opts = Vec::new();
+    let mut argopts = Vec::new();
+    for spec in &cmd.opts {
+      let spec = spec.borrow();
+      let names = spec.get_opt_strs();
+      if spec.req_args() {
+        argopts.extend(names.iter().cloned());
+      }
+      opts.extend(names);
+    }
+    let subcmds: Vec<&str> =
+      cmd.prsr.subcmds.iter().map(|sub| sub.name.as_str()).collect();
+    let posargs = if cmd.prsr.posargs.is_empty() { "" } else { "1" };
+
+    let mut path = vec![name];
+    path.extend(&cmd.path);
+    s.push_str(&format!("    {})\n", sh_quote(&path.join(" "))));
+    s.push_str(&format!("      opts={}\n", sh_quote(&opts.join(" "))));
+    s.push_str(&format!("      argopts={}\n", sh_quote(&argopts.join(" "))));
+    s.push_str(&format!("      cmds={}\n", sh_quote(&subcmds.join(" "))));
+    s.push_str(&format!("      posargs={}\n", sh_quote(posargs)));
+    s.push_str("      ;;\n");
+  }
+  s.push_str("  esac\n\n");
+
+  // Options with a restricted set of values.
+  let mut choices = String::new();
+  for cmd in cmds {
+    let mut path = vec![name];
+    path.extend(&cmd.path);
+    for spec in &cmd.opts {
+      let spec = spec.borrow();
+      let vals = match spec.get_choices() {
+        Some(vals) if spec.req_args() => vals,
+        _ => continue
+      };
+      let pats: Vec<String> = spec
+        .get_opt_strs()
+        .into_iter()
+        .map(|opt| sh_quote(&format!("{},{}", path.join(" "), opt)))
+        .collect();
+      choices.push_str(&format!("    {})\n", pats.join("|")));
+      choices.push_str(&format!(
+        "      COMPREPLY=( $(compgen -W {} -- \"${{cur}}\") )\n",
+        sh_quote(&vals.join(" "))
+      ));
+      choices.push_str("      return 0\n");
+      choices.push_str("      ;;\n");
+    }
+  }
+  if !choices.is_empty() {
+    s.push_str("  case \"${cmd},${prev}\" in\n");
+    s.push_str(&choices);
+    s.push_str("  esac\n\n");
+  }
+
+  s.push_str("  case \" ${argopts} \" in\n");
+  s.push_str("    *\" ${prev} \"*)\n");
+  s.push_str("      COMPREPLY=( $(compgen -f -- \"${cur}\") )\n");
+  s.push_str("      return 0\n");
+  s.push_str("      ;;\n");
+  s.push_str("  esac\n\n");
+
+  s.push_str("  if [[ \"${cur}\" == -* ]]; then\n");
+  s.push_str("    COMPREPLY=( $(compgen -W \"${opts}\" -- \"${cur}\") )\n");
+  s.push_str("  elif [[ -n \"${cmds}\" ]]; then\n");
+  s.push_str("    COMPREPLY=( $(compgen -W \"${cmds}\" -- \"${cur}\") )\n");
+  s.push_str("  elif [[ -n \"${posargs}\" ]]; then\n");
+  s.push_str("    COMPREPLY=( $(compgen -f -- \"${cur}\") )\n");
+  s.push_str("  fi\n");
+  s.push_str("  return 0\n");
+  s.push_str("}\n\n");
+
+  s.push_str(&format!(
+    "complete -F {} -o bashdefault -o default {}\n",
+    func,
+    sh_quote(name)
+  ));
+
+  s
+}
+
+
+/// Escape a description for use within brackets in a zsh `_arguments` spec.
+fn zsh_desc(desc: &str) -> String {
+  desc
+    .replace('\\', "\\\\")
+    .replace('[', "\\[")
+    .replace(']', "\\]")
+}
+
+/// Escape a zsh `_arguments` message or `_describe` item.
+fn zsh_colon(s: &str) -> String {
+  s.replace('\\', "\\\\").replace(':', "\\:")
+}
+
+/// Get the zsh action for completing a spec's arguments: its choices if it
+/// has any, otherwise file names.
+fn zsh_action<C>(spec: &Spec<C>) -> String {
+  match spec.get_choices() {
+    Some(choices) => {
+      let choices: Vec<String> = choices
+        .iter()
+        .map(|c| zsh_colon(c).replace(' ', "\\ "))
+        .collect();
+      format!("({})", choices.join(" "))
+    }
+    None => String::from("_files")
+  }
+}
+
+/// Generate the argument part of a zsh option spec.
+fn zsh_optargs<C>(spec: &Spec<C>) -> String {
+  let metaname = |i: usize| -> String {
+    zsh_colon(spec.metanames.get(i).map(|s| s.as_str()).unwrap_or("ARG"))
+  };
+  let action = zsh_action(spec);
+  match spec.nargs() {
+    Nargs::None => String::new(),
+    Nargs::Count(n) => (0..n)
+      .map(|i| format!(":{}:{}", metaname(i), action))
+      .collect(),
+    Nargs::Optional => format!("::{}:{}", metaname(0), action),
+    _ => format!(":{}:{}", metaname(0), action)
+  }
+}
+
+fn gen_zsh<C>(name: &str, cmds: &[Cmd<C>]) -> String {
+  let mut s = String::new();
+
+  s.push_str(&format!("#compdef {}\n", name));
+
+  for cmd in cmds {
+    let func = format!("_{}", ident(name, &cmd.path));
+    s.push('\n');
+    s.push_str(&format!("{}() {{\n", func));
+    s.push_str("  local context state state_descr line\n");
+    s.push_str("  typeset -A opt_args\n\n");
+    s.push_str("  _arguments -C \\\n");
+
+    for spec in &cmd.opts {
+      let spec = spec.borrow();
+      let desc = zsh_desc(&get_desc(spec.get_help_text()));
+      let args = zsh_optargs(&spec);
+      let names = spec.get_opt_strs();
+      let line = match names.len() {
+        0 => continue,
+        1 => sh_quote(&format!("{}[{}]{}", names[0], desc, args)),
+        _ => format!(
+          "{}{{{}}}{}",
+          sh_quote(&format!("({})", names.join(" "))),
+          names.join(","),
+          sh_quote(&format!("[{}]{}", desc, args))
+        )
+      };
+      s.push_str(&format!("    {} \\\n", line));
+    }
+
+    // Positional arguments.  Sub-commands are dispatched once all positional
+    // arguments have been consumed.
+    let mut fixed = 0;
+    let mut rest = false;
+    for spec in &cmd.prsr.posargs {
+      let spec = spec.borrow();
+      let action = zsh_action(&spec);
+      let metaname =
+        zsh_colon(spec.metanames.first().map(|s| s.as_str()).unwrap_or("ARG"));
+      match spec.nargs() {
+        Nargs::None => {}
+        Nargs::Count(n) => {
+          for i in 0..n {
+            let metaname = zsh_colon(
+              spec.metanames.get(i).map(|s| s.as_str()).unwrap_or("ARG")
+            );
+            s.push_str(&format!(
+              "    {} \\\n",
+              sh_quote(&format!(":{}:{}", metaname, action))
+            ));
+          }
+          fixed += n;
+        }
+        Nargs::Optional => {
+          s.push_str(&format!(
+            "    {} \\\n",
+            sh_quote(&format!("::{}:{}", metaname, action))
+          ));
+        }
+        _ => {
+          s.push_str(&format!(
+            "    {} \\\n",
+            sh_quote(&format!("*:{}:{}", metaname, action))
+          ));
+          rest = true;
+          break;
+        }
+      }
+    }
+
+    if !cmd.prsr.subcmds.is_empty() && !rest {
+      s.push_str("    ':command:->cmds' \\\n");
+      s.push_str("    '*::arg:->args' \\\n");
+    }
+    s.push_str("    && return 0\n");
+
+    if !cmd.prsr.subcmds.is_empty() && !rest {
+      s.push('\n');
+      s.push_str("  case $state in\n");
+      s.push_str("    cmds)\n");
+      s.push_str("      local -a cmds\n");
+      s.push_str("      cmds=(\n");
+      for sub in &cmd.prsr.subcmds {
+        let item =
+          format!("{}:{}", zsh_colon(&sub.name), get_desc(&sub.help));
+        s.push_str(&format!("        {}\n", sh_quote(&item)));
+      }
+      s.push_str("      )\n");
+      s.push_str("      _describe 'command' cmds\n");
+      s.push_str("      ;;\n");
+      s.push_str("    args)\n");
+      s.push_str(&format!("      case $line[{}] in\n", fixed + 1));
+      for sub in &cmd.prsr.subcmds {
+        let mut path = cmd.path.clone();
+        path.push(&sub.name);
+        s.push_str(&format!(
+          "        {}) _{} ;;\n",
+          sh_quote(&sub.name),
+          ident(name, &path)
+        ));
+      }
+      s.push_str("      esac\n");
+      s.push_str("      ;;\n");
+      s.push_str("  esac\n");
+    }
+
+    s.push_str("}\n");
+  }
+
+  s.push('\n');
+  s.push_str(&format!("_{} \"$@\"\n", ident(name, &[])));
+
+  s
+}
+
+
+fn gen_fish<C>(name: &str, cmds: &[Cmd<C>]) -> String {
+  let mut s = String::new();
+  let cmdname = fish_quote(name);
+
+  for cmd in cmds {
+    // Condition under which the options/sub-commands of this command apply:
+    // every sub-command on the path to it has been given, but none of its
+    // own sub-commands.
+    let mut conds: Vec<String> = cmd
+      .path
+      .iter()
+      .map(|name| format!("__fish_seen_subcommand_from {}", name))
+      .collect();
+    if !cmd.prsr.subcmds.is_empty() {
+      if conds.is_empty() {
+        conds.push("__fish_use_subcommand".to_string());
+      } else {
+        let names: Vec<&str> =
+          cmd.prsr.subcmds.iter().map(|x| x.name.as_str()).collect();
+        conds.push(format!(
+          "not __fish_seen_subcommand_from {}",
+          names.join(" ")
+        ));
+      }
+    }
+    let cond = (!conds.is_empty()).then(|| conds.join("; and "));
+
+    for spec in &cmd.opts {
+      let spec = spec.borrow();
+      let mut line = format!("complete -c {}", cmdname);
+      // Global options of the top level parser apply everywhere.
+      if let Some(ref cond) = cond {
+        if !(cmd.path.is_empty() && spec.is_global()) {
+          line.push_str(&format!(" -n {}", fish_quote(cond)));
+        }
+      }
+      for sopt in spec.sopt_names(false) {
+        line.push_str(&format!(" -s {}", fish_quote(&sopt.to_string())));
+      }
+      for lopt in spec.lopt_names(false, true) {
+        line.push_str(&format!(" -l {}", fish_quote(&lopt)));
+      }
+      match spec.get_choices() {
+        Some(choices) if spec.takes_args() => {
+          line.push_str(&format!(" -x -a {}", fish_quote(&choices.join(" "))));
+        }
+        _ if spec.req_args() => line.push_str(" -r"),
+        _ => {}
+      }
+      let desc = get_desc(spec.get_help_text());
+      if !desc.is_empty() {
+        line.push_str(&format!(" -d {}", fish_quote(&desc)));
+      }
+      s.push_str(&line);
+      s.push('\n');
+    }
+
+    for sub in &cmd.prsr.subcmds {
+      let mut line = format!("complete -c {}", cmdname);
+      if let Some(ref cond) = cond {
+        line.push_str(&format!(" -n {}", fish_quote(cond)));
+      }
+      line.push_str(&format!(" -f -a {}", fish_quote(&sub.name)));
+      let desc = get_desc(&sub.help);
+      if !desc.is_empty() {
+        line.push_str(&format!(" -d {}", fish_quote(&desc)));
+      }
+      s.push_str(&line);
+      s.push('\n');
+    }
+  }
+
+  s
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,190 @@
+//! Conversion of arguments to typed values.
+
+use std::ffi::{OsStr, OsString};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use crate::spec::Spec;
+
+
+/// Conversion of a single argument to a value.
+///
+/// This is implemented for the standard library's primitive types, strings,
+/// paths and network addresses.  Implement it for your own types to use them
+/// with [`Builder::build_typed()`](crate::Builder::build_typed); this is also
+/// the place to put custom parsers.
+pub trait FromArg: Sized {
+  /// Convert an argument, returning an error message if it is not valid.
+  fn from_arg(arg: &OsStr) -> Result<Self, String>;
+}
+
+macro_rules! from_str_impl {
+  ($($t:ty),*) => {
+    $(
+      impl FromArg for $t {
+        fn from_arg(arg: &OsStr) -> Result<Self, String> {
+          match arg.to_str() {
+            Some(s) => s.parse::<$t>().map_err(|e| e.to_string()),
+            None => Err("Argument is not valid UTF-8".to_string())
+          }
+        }
+      }
+    )*
+  };
+}
+
+from_str_impl!(
+  u8,
+  u16,
+  u32,
+  u64,
+  u128,
+  usize,
+  i8,
+  i16,
+  i32,
+  i64,
+  i128,
+  isize,
+  f32,
+  f64,
+  bool,
+  char,
+  String,
+  std::net::IpAddr,
+  std::net::Ipv4Addr,
+  std::net::Ipv6Addr,
+  std::net::SocketAddr
+);
+
+// Paths and OS strings are taken as-is, so they don't need to be UTF-8.
+impl FromArg for PathBuf {
+  fn from_arg(arg: &OsStr) -> Result<Self, String> {
+    Ok(PathBuf::from(arg))
+  }
+}
+
+impl FromArg for OsString {
+  fn from_arg(arg: &OsStr) -> Result<Self, String> {
+    Ok(arg.to_os_string())
+  }
+}
+
+
+/// Conversion of all of a spec's arguments to a value.
+///
+/// The type determines how many arguments are expected, which should match
+/// the spec's [`Nargs`](crate::Nargs):
+/// - `()` for no arguments.
+/// - Any [`FromArg`] type for exactly one argument.
+/// - `Option<T>` for zero or one argument.
+/// - `Vec<T>` for any number of arguments.
+/// - Tuples of up to four [`FromArg`] types for that many arguments.
+pub trait FromArgs: Sized {
+  /// Convert the arguments.  On failure, return the index of the offending
+  /// argument (which is the number of arguments if there were too few) and an
+  /// error message.
+  fn from_args(args: &[OsString]) -> Result<Self, (usize, String)>;
+}
+
+/// Make sure the expected number of arguments were passed.
+fn check_count(args: &[OsString], n: usize) -> Result<(), (usize, String)> {
+  if args.len() != n {
+    let msg = format!("Expected {} argument(s), got {}", n, args.len());
+    return Err((args.len().min(n), msg));
+  }
+  Ok(())
+}
+
+impl FromArgs for () {
+  fn from_args(args: &[OsString]) -> Result<Self, (usize, String)> {
+    check_count(args, 0)
+  }
+}
+
+impl<T: FromArg> FromArgs for T {
+  fn from_args(args: &[OsString]) -> Result<Self, (usize, String)> {
+    check_count(args, 1)?;
+    T::from_arg(&args[0]).map_err(|msg| (0, msg))
+  }
+}
+
+impl<T: FromArg> FromArgs for Option<T> {
+  fn from_args(args: &[OsString]) -> Result<Self, (usize, String)> {
+    match args.len() {
+      0 => Ok(None),
+      _ => T::from_args(args).map(Some)
+    }
+  }
+}
+
+impl<T: FromArg> FromArgs for Vec<T> {
+  fn from_args(args: &[OsString]) -> Result<Self, (usize, String)> {
+    args
+      .iter()
+      .enumerate()
+      .map(|(i, arg)| T::from_arg(arg).map_err(|msg| (i, msg)))
+      .collect()
+  }
+}
+
+macro_rules! tuple_impl {
+  ($n:expr; $($t:ident $i:tt),*) => {
+    impl<$($t: FromArg),*> FromArgs for ($($t,)*) {
+      fn from_args(args: &[OsString]) -> Result<Self, (usize, String)> {
+        check_count(args, $n)?;
+        Ok(($(
+          $t::from_arg(&args[$i]).map_err(|msg| ($i, msg))?,
+        )*))
+      }
+    }
+  };
+}
+
+tuple_impl!(2; A 0, B 1);
+tuple_impl!(3; A 0, B 1, C 2);
+tuple_impl!(4; A 0, B 1, C 2, D 3);
+
+
+/// Failure of a typed handler.
+pub(crate) enum TypedErr {
+  /// Argument conversion failed; the index of the argument and a message.
+  Conv(usize, String),
+
+  /// The handler rejected the converted value.
+  Handler(String)
+}
+
+/// A handler which takes its arguments converted to a value.
+pub(crate) trait TypedProc<C> {
+  fn call(
+    &mut self,
+    spec: &Spec<C>,
+    ctx: &mut C,
+    args: &[OsString]
+  ) -> Result<(), TypedErr>;
+}
+
+/// A typed handler along with the type it wants its arguments converted to.
+pub(crate) struct Typed<T, F> {
+  pub(crate) proc: F,
+  pub(crate) ty: PhantomData<fn() -> T>
+}
+
+impl<C, T, F> TypedProc<C> for Typed<T, F>
+where
+  T: FromArgs,
+  F: FnMut(&Spec<C>, &mut C, T) -> Result<(), String>
+{
+  fn call(
+    &mut self,
+    spec: &Spec<C>,
+    ctx: &mut C,
+    args: &[OsString]
+  ) -> Result<(), TypedErr> {
+    let val = T::from_args(args).map_err(|(i, msg)| TypedErr::Conv(i, msg))?;
+    (self.proc)(spec, ctx, val).map_err(TypedErr::Handler)
+  }
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
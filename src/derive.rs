@@ -0,0 +1,102 @@
+//! Specs generated from annotated context structs.
+
+use std::ffi::OsStr;
+
+use crate::err::ErrKind;
+use crate::parser::Parser;
+
+
+/// A context struct which knows which specs populate it.
+///
+/// With the `derive` feature enabled, this can be derived for structs with
+/// named fields.  Each field with a `#[qarg(...)]` attribute becomes a spec
+/// whose handler stores the field's value, converted using
+/// [`FromArgs`](crate::FromArgs).  Fields without the attribute are left at
+/// their initial values.
+///
+/// The attribute takes the following keys:
+/// - `sopt = 'c'` and `lopt = "name"` make the field an option; a bare
+///   `lopt` uses the field name, with underscores replaced by dashes.
+///   Fields with neither are positional arguments.
+/// - `help = "..."` adds a help paragraph.  Without it, the field's doc
+///   comment is used.
+/// - `nargs = 2`, or one of `"?"`, `"*"`, `"+"` and `"..."`, overrides the
+///   number of arguments implied by the field's type.  `meta = "NAME"` sets
+///   the arguments' meta-names, and can be repeated.
+/// - `required`, `hidden` and `exit` correspond to the `Builder` methods of
+///   the same names.
+/// - `name = "..."` overrides the spec name, which defaults to the field
+///   name.
+///
+/// The field's type determines how its arguments are stored:
+/// - `bool` is set to `true` if the spec is matched, and takes no arguments.
+///   It must be an option.
+/// - `Option<T>` is set to `Some` value.  With `nargs = "?"` or `"*"` the
+///   option may be given without a value, which sets it to `None`.  These
+///   two are only allowed for `Option` and `Vec` fields.
+/// - `Vec<T>` is extended each time the spec is matched.  Options take one
+///   value per match, which may be a tuple.  Positional arguments take any
+///   number of arguments, or at least one if `required`.
+/// - Any other type is set to the last value.  Tuples take one argument per
+///   element.
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use qargparser::QArgs;
+///
+/// #[derive(Default, QArgs)]
+/// struct Ctx {
+///   /// Show this help.
+///   #[qarg(sopt = 'h', lopt, exit)]
+///   help: bool,
+///
+///   /// Port to listen on.
+///   #[qarg(sopt = 'p', lopt, meta = "PORT")]
+///   port: Option<u16>,
+///
+///   #[qarg(help = "Files to serve.")]
+///   files: Vec<std::path::PathBuf>
+/// }
+///
+/// let mut prsr = Ctx::from_args("tool", &["-p", "80", "index.html"])?;
+/// prsr.parse()?;
+/// assert_eq!(prsr.get_ctx().port, Some(80));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "derive"))]
+/// # fn main() {}
+/// ```
+pub trait QArgs: Sized {
+  /// Add the struct's specs to a parser.
+  fn add_specs(prsr: &mut Parser<Self>) -> Result<(), ErrKind<Self>>;
+
+  /// Create a parser for the process' command line arguments, with a
+  /// default context and the struct's specs.
+  fn from_env() -> Result<Parser<Self>, ErrKind<Self>>
+  where
+    Self: Default
+  {
+    let mut prsr = Parser::from_env(Self::default());
+    Self::add_specs(&mut prsr)?;
+    Ok(prsr)
+  }
+
+  /// Create a parser for an explicit list of arguments, with a default
+  /// context and the struct's specs.
+  fn from_args<I, S>(
+    argv0: &str,
+    args: I
+  ) -> Result<Parser<Self>, ErrKind<Self>>
+  where
+    Self: Default,
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>
+  {
+    let mut prsr = Parser::from_args(argv0, args, Self::default());
+    Self::add_specs(&mut prsr)?;
+    Ok(prsr)
+  }
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
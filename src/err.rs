@@ -1,8 +1,11 @@
 use std::cell::RefCell;
 use std::error::Error as StdError;
+use std::ffi::OsString;
 use std::fmt;
+use std::path::PathBuf;
 use std::rc::Rc;
 
+use crate::group::Group;
 use crate::spec::Spec;
 
 #[derive(Clone)]
@@ -18,13 +21,248 @@ impl<C> fmt::Display for SpecErr<C> {
 }
 
 
+/// An argument which was rejected by a spec's handler.
+#[derive(Clone)]
+pub struct ArgErr<C> {
+  /// The spec whose handler rejected the argument(s).
+  pub spec: Rc<RefCell<Spec<C>>>,
+
+  /// The raw arguments which were passed to the handler.
+  pub args: Vec<OsString>,
+
+  /// The error message returned by the handler.
+  pub msg: String
+}
+
+impl<C> fmt::Display for ArgErr<C> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let args = self
+      .args
+      .iter()
+      .map(|a| a.to_string_lossy())
+      .collect::<Vec<_>>()
+      .join(" ");
+    f.write_fmt(format_args!("'{}'; {}", args, self.msg))
+  }
+}
+
+
+/// An option which the parser does not know about.
+#[derive(Clone, Debug)]
+pub struct OptErr {
+  /// The option as it was given on the command line, like `--verbse`.
+  pub opt: String,
+
+  /// A registered option which is similar enough to `opt` that it was
+  /// probably what the user meant, like `--verbose`.
+  pub suggestion: Option<String>,
+
+  pub msg: String
+}
+
+impl fmt::Display for OptErr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.suggestion {
+      Some(ref s) => {
+        f.write_fmt(format_args!("{}; did you mean {}?", self.msg, s))
+      }
+      None => f.write_fmt(format_args!("{}", self.msg))
+    }
+  }
+}
+
+
+/// An abbreviated long option which matches more than one option.
+#[derive(Clone, Debug)]
+pub struct AmbigErr {
+  /// The option as it was given on the command line, like `--ver`.
+  pub opt: String,
+
+  /// The options it could be an abbreviation of, like `--verbose` and
+  /// `--version`.
+  pub candidates: Vec<String>
+}
+
+impl fmt::Display for AmbigErr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_fmt(format_args!(
+      "'{}' could be {}",
+      self.opt,
+      self.candidates.join(", ")
+    ))
+  }
+}
+
+
+/// Required options which did not appear on the command line.
+#[derive(Clone)]
+pub struct MissOptErr<C> {
+  /// The specs of the missing options.
+  pub specs: Vec<Rc<RefCell<Spec<C>>>>,
+
+  /// The usage strings of the missing options, like `<--config FILE>`.
+  pub opts: Vec<String>
+}
+
+impl<C> fmt::Display for MissOptErr<C> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_fmt(format_args!("{}", self.opts.join(", ")))
+  }
+}
+
+
+/// A violated option group constraint.
+#[derive(Clone)]
+pub struct GroupErr<C> {
+  /// The kind of group whose constraint was violated.
+  pub kind: Group,
+
+  /// The specs of the options involved.  For conflicts and missing
+  /// dependencies these are the two options in question.
+  pub specs: Vec<Rc<RefCell<Spec<C>>>>,
+
+  /// The names of the options involved, like `--json`.
+  pub opts: Vec<String>,
+
+  pub msg: String
+}
+
+impl<C> fmt::Display for GroupErr<C> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_fmt(format_args!("{}", self.msg))
+  }
+}
+
+
+/// An argument which is not one of a spec's choices.
+#[derive(Clone)]
+pub struct ChoiceErr<C> {
+  /// The spec whose choices the argument didn't match.
+  pub spec: Rc<RefCell<Spec<C>>>,
+
+  /// The offending argument.
+  pub arg: OsString,
+
+  /// The valid choices.
+  pub choices: Vec<String>
+}
+
+impl<C> fmt::Display for ChoiceErr<C> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_fmt(format_args!(
+      "'{}'; expected one of {}",
+      self.arg.to_string_lossy(),
+      self.choices.join(", ")
+    ))
+  }
+}
+
+
+/// An argument which could not be converted to a typed handler's value type.
+#[derive(Clone)]
+pub struct ConvErr<C> {
+  /// The spec whose argument failed to convert.
+  pub spec: Rc<RefCell<Spec<C>>>,
+
+  /// The offending argument, or `None` if there were too few arguments.
+  pub arg: Option<OsString>,
+
+  /// The index of the offending argument among the spec's arguments.
+  pub index: usize,
+
+  pub msg: String
+}
+
+impl<C> fmt::Display for ConvErr<C> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match &self.arg {
+      Some(arg) => f.write_fmt(format_args!(
+        "'{}'; {}",
+        arg.to_string_lossy(),
+        self.msg
+      )),
+      None => f.write_fmt(format_args!("{}", self.msg))
+    }
+  }
+}
+
+
+/// A response file which could not be expanded.
+#[derive(Clone, Debug)]
+pub struct RespFileErr {
+  /// The path of the response file, as it was given.
+  pub path: PathBuf,
+
+  pub msg: String
+}
+
+impl fmt::Display for RespFileErr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_fmt(format_args!("'{}'; {}", self.path.display(), self.msg))
+  }
+}
+
+
+/// An error caused by arguments taken from an environment variable by
+/// [`Parser::from_env_with_prefix_var()`](
+/// crate::Parser::from_env_with_prefix_var).
+#[derive(Clone)]
+pub struct VarErr<C> {
+  /// The name of the environment variable.
+  pub var: String,
+
+  pub msg: String,
+
+  /// The error the variable's arguments caused, or `None` if the variable
+  /// could not be split into arguments.
+  pub err: Option<Box<ErrKind<C>>>
+}
+
+impl<C> fmt::Display for VarErr<C> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_fmt(format_args!("'{}'; {}", self.var, self.msg))
+  }
+}
+
+
 #[derive(Clone)]
 pub enum ErrKind<C> {
   MissArg(SpecErr<C>),
+
+  /// One or more required options are missing.
+  MissOpt(MissOptErr<C>),
+
   MissSpec(String),
   BadContext(String),
-  UnknownOpt(String),
-  Collision(String)
+  UnknownOpt(OptErr),
+
+  /// An abbreviated long option matches more than one option.
+  Ambiguous(AmbigErr),
+
+  Collision(String),
+  UnknownCmd(String),
+
+  /// A spec's handler rejected its argument(s).
+  BadArg(ArgErr<C>),
+
+  /// An argument is not one of the spec's choices.
+  BadChoice(ChoiceErr<C>),
+
+  /// An argument could not be converted to a typed handler's value type.
+  Conv(ConvErr<C>),
+
+  /// A response file could not be read or parsed.
+  RespFile(RespFileErr),
+
+  /// Arguments taken from an environment variable caused an error.
+  PrefixVar(VarErr<C>),
+
+  /// The options used violate an option group's constraint.
+  Group(GroupErr<C>),
+
+  /// A spec whose handler expects UTF-8 strings received an argument which is
+  /// not valid UTF-8.
+  InvalidUtf8(SpecErr<C>)
 }
 
 impl<C> StdError for ErrKind<C> {}
@@ -32,10 +270,13 @@ impl<C> StdError for ErrKind<C> {}
 
 impl<C> fmt::Display for ErrKind<C> {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    match &*self {
+    match self {
       ErrKind::MissArg(s) => {
         f.write_fmt(format_args!("Missing argument ({})", s))
       }
+      ErrKind::MissOpt(s) => {
+        f.write_fmt(format_args!("Missing required option(s) {}", s))
+      }
       ErrKind::MissSpec(s) => {
         f.write_fmt(format_args!("Missing argspec; {}", s))
       }
@@ -45,19 +286,48 @@ impl<C> fmt::Display for ErrKind<C> {
       ErrKind::UnknownOpt(s) => {
         f.write_fmt(format_args!("Unknown option; {}", s))
       }
+      ErrKind::Ambiguous(s) => {
+        f.write_fmt(format_args!("Ambiguous option; {}", s))
+      }
       ErrKind::Collision(s) => {
         f.write_fmt(format_args!("Colliding options; {}", s))
       }
+      ErrKind::UnknownCmd(s) => {
+        f.write_fmt(format_args!("Unknown command; {}", s))
+      }
+      ErrKind::BadArg(s) => {
+        f.write_fmt(format_args!("Bad argument {}", s))
+      }
+      ErrKind::BadChoice(s) => {
+        f.write_fmt(format_args!("Invalid choice {}", s))
+      }
+      ErrKind::Conv(s) => f.write_fmt(format_args!("Invalid value {}", s)),
+      ErrKind::RespFile(s) => {
+        f.write_fmt(format_args!("Bad response file {}", s))
+      }
+      ErrKind::PrefixVar(s) => f.write_fmt(format_args!(
+        "Bad arguments in environment variable {}",
+        s
+      )),
+      ErrKind::Group(s) => {
+        f.write_fmt(format_args!("Invalid option combination; {}", s))
+      }
+      ErrKind::InvalidUtf8(s) => {
+        f.write_fmt(format_args!("Invalid UTF-8 argument ({})", s))
+      }
     }
   }
 }
 
 impl<C> fmt::Debug for ErrKind<C> {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    match &*self {
+    match self {
       ErrKind::MissArg(s) => {
         f.write_fmt(format_args!("Missing argument ({})", s))
       }
+      ErrKind::MissOpt(s) => {
+        f.write_fmt(format_args!("Missing required option(s) {}", s))
+      }
       ErrKind::MissSpec(s) => {
         f.write_fmt(format_args!("Missing argspec; {}", s))
       }
@@ -67,9 +337,35 @@ impl<C> fmt::Debug for ErrKind<C> {
       ErrKind::UnknownOpt(s) => {
         f.write_fmt(format_args!("Unknown option; {}", s))
       }
+      ErrKind::Ambiguous(s) => {
+        f.write_fmt(format_args!("Ambiguous option; {}", s))
+      }
       ErrKind::Collision(s) => {
         f.write_fmt(format_args!("Colliding options; {}", s))
       }
+      ErrKind::UnknownCmd(s) => {
+        f.write_fmt(format_args!("Unknown command; {}", s))
+      }
+      ErrKind::BadArg(s) => {
+        f.write_fmt(format_args!("Bad argument {}", s))
+      }
+      ErrKind::BadChoice(s) => {
+        f.write_fmt(format_args!("Invalid choice {}", s))
+      }
+      ErrKind::Conv(s) => f.write_fmt(format_args!("Invalid value {}", s)),
+      ErrKind::RespFile(s) => {
+        f.write_fmt(format_args!("Bad response file {}", s))
+      }
+      ErrKind::PrefixVar(s) => f.write_fmt(format_args!(
+        "Bad arguments in environment variable {}",
+        s
+      )),
+      ErrKind::Group(s) => {
+        f.write_fmt(format_args!("Invalid option combination; {}", s))
+      }
+      ErrKind::InvalidUtf8(s) => {
+        f.write_fmt(format_args!("Invalid UTF-8 argument ({})", s))
+      }
     }
   }
 }
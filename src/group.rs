@@ -0,0 +1,124 @@
+//! Option groups.
+
+use std::rc::Rc;
+
+use crate::err::{ErrKind, GroupErr};
+use crate::parser::SpecRc;
+
+
+/// Kinds of option groups which can be registered using
+/// [`Parser::add_group()`](crate::Parser::add_group).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Group {
+  /// At most one of the options may be used, like `[--json | --yaml]`.
+  AtMostOne,
+
+  /// Exactly one of the options must be used, like `<--file F | --stdin>`.
+  ExactlyOne,
+
+  /// If the first option is used, then all the other options must be used
+  /// as well.
+  Requires
+}
+
+
+pub(crate) struct OptGroup<C> {
+  pub(crate) kind: Group,
+  pub(crate) specs: Vec<SpecRc<C>>
+}
+
+
+/// Get the name an option is referred to by in errors and group usage.
+pub(crate) fn get_opt_name<C>(spec: &SpecRc<C>) -> String {
+  let spec = spec.borrow();
+  match spec.get_lopt_str() {
+    Some(s) => s,
+    None => spec.get_sopt_str().unwrap_or_default()
+  }
+}
+
+
+impl<C> OptGroup<C> {
+  /// Make sure the options which were used satisfy the group's constraint.
+  pub(crate) fn validate(&self) -> Result<(), ErrKind<C>> {
+    let seen: Vec<&SpecRc<C>> =
+      self.specs.iter().filter(|s| s.borrow().seen > 0).collect();
+
+    match self.kind {
+      Group::AtMostOne | Group::ExactlyOne if seen.len() > 1 => {
+        let specs = vec![Rc::clone(seen[0]), Rc::clone(seen[1])];
+        let msg = format!(
+          "{} can't be used together with {}",
+          get_opt_name(seen[0]),
+          get_opt_name(seen[1])
+        );
+        return Err(self.mkerr(specs, msg));
+      }
+      Group::AtMostOne => {}
+      Group::ExactlyOne => {
+        if seen.is_empty() {
+          let specs = self.specs.clone();
+          let names: Vec<String> = specs.iter().map(get_opt_name).collect();
+          let msg = format!("One of {} is required", names.join(", "));
+          return Err(self.mkerr(specs, msg));
+        }
+      }
+      Group::Requires => {
+        if self.specs[0].borrow().seen > 0 {
+          for spec in &self.specs[1..] {
+            if spec.borrow().seen == 0 {
+              let specs = vec![Rc::clone(&self.specs[0]), Rc::clone(spec)];
+              let msg = format!(
+                "{} requires {}",
+                get_opt_name(&self.specs[0]),
+                get_opt_name(spec)
+              );
+              return Err(self.mkerr(specs, msg));
+            }
+          }
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn mkerr(&self, specs: Vec<SpecRc<C>>, msg: String) -> ErrKind<C> {
+    let opts = specs.iter().map(get_opt_name).collect();
+    ErrKind::Group(GroupErr {
+      kind: self.kind,
+      specs,
+      opts,
+      msg
+    })
+  }
+
+  /// Generate the usage string for groups which are shown as a single unit,
+  /// like `[--json | --yaml]`.  Hidden options are left out.
+  pub(crate) fn get_usage_str(&self) -> Option<String> {
+    let (open, close) = match self.kind {
+      Group::AtMostOne => ('[', ']'),
+      Group::ExactlyOne => ('<', '>'),
+      Group::Requires => return None
+    };
+
+    let alts: Vec<String> = self
+      .specs
+      .iter()
+      .filter_map(|spec| {
+        let spec = spec.borrow();
+        if spec.is_hidden() {
+          return None;
+        }
+        spec.get_loptarg_str().or_else(|| spec.get_soptarg_str())
+      })
+      .collect();
+    if alts.is_empty() {
+      return None;
+    }
+
+    Some(format!("{}{}{}", open, alts.join(" | "), close))
+  }
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
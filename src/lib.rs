@@ -7,7 +7,8 @@
 //! 2. Create one or more [`Builder`].
 //! 3. Create [`Spec`] objects from the [`Builder`] objects
 //!    by calling [`Builder::build()`](Builder::build), passing a function for
-//!    processing the option/argument.
+//!    processing the option/argument.  Closures which need to capture state
+//!    can be passed to [`Builder::build_fn()`](Builder::build_fn) instead.
 //! 4. Create a [`Parser`] object ([`Parser::from_env()`](Parser::from_env)
 //!    or [`Parser::from_args()`](Parser::from_args)) handing over the
 //!    ownership of a parser context struct.
@@ -22,18 +23,98 @@
 //! One of the minor goals of qargparser is to simplify reuse of
 //! option/argument specs between parsers.
 //!
-//! # ToDo
-//! - Currently converts argument strings to UTF-8.  Should support
-//!   `OsStr(ing)`.
+//! # Rejecting arguments
+//! Handlers passed to [`Builder::try_build()`](Builder::try_build) return a
+//! `Result`.  If the handler returns an error message the parser fails with
+//! [`ErrKind::BadArg`], which holds the spec, the raw arguments and the
+//! message.
+//!
+//! # Typed arguments
+//! Handlers passed to [`Builder::build_typed()`](Builder::build_typed)
+//! receive their arguments already converted, e.g. to a `u16` or a
+//! `(String, PathBuf)` pair.  Conversion is done by the [`FromArgs`] and
+//! [`FromArg`] traits, and failures are reported as [`ErrKind::Conv`].
+//!
+//! # Matches
+//! Besides calling the handlers, the parser records what it saw of each spec
+//! in a [`Matches`] map, keyed by spec name: whether and how often it was
+//! matched, its arguments and their positions.  Quick tools can skip the
+//! handlers altogether by building specs using
+//! [`Builder::build_match()`](Builder::build_match) and querying
+//! [`Parser::get_matches()`](Parser::get_matches) after parsing.
+//!
+//! # Deriving specs
+//! With the `derive` feature enabled, `#[derive(QArgs)]` generates the specs
+//! and handlers for a context struct from attributes on its fields; see
+//! [`QArgs`].
+//!
+//! # Sub-commands
+//! Tools in the style of `tool [global options] <command> [command options]`
+//! can register a child parser per command using
+//! [`Parser::add_subcmd()`](Parser::add_subcmd).  Options built with
+//! [`Builder::global()`](Builder::global) are inherited by the child parsers.
+//!
+//! # Non-UTF-8 arguments
+//! The parser keeps the command line arguments as `OsString`s.  Handlers
+//! passed to [`Builder::build()`](Builder::build) receive their arguments as
+//! `String`s, and the parser will return [`ErrKind::InvalidUtf8`] if an
+//! argument can not be converted.  Handlers passed to
+//! [`Builder::build_os()`](Builder::build_os) receive the arguments as
+//! `OsString`s, unaltered.
+//!
+//! # Deprecated options
+//! Specs built with [`Builder::deprecated()`](Builder::deprecated), and
+//! aliases added with
+//! [`Builder::deprecated_lopt_alias()`](Builder::deprecated_lopt_alias),
+//! keep working but cause a [`Warning`] when used; see
+//! [`Parser::get_warnings()`](Parser::get_warnings).
+//!
+//! # Response files
+//! Parsers can read additional arguments from files given as `@path`; see
+//! [`Parser::set_response_files()`](Parser::set_response_files).
+//!
+//! # Passing options through
+//! Wrapper tools can collect the options they don't know, instead of failing,
+//! and forward them to a child process; see
+//! [`Parser::set_passthrough()`](Parser::set_passthrough).
+//!
+//! # Shell completion
+//! [`Parser::completion()`](Parser::completion) writes a bash, zsh or fish
+//! completion script for a parser and its sub-commands.  Hidden specs are
+//! left out.
+//!
+//! # Man pages
+//! [`Parser::man()`](Parser::man) writes a roff man page generated from the
+//! same specs and help texts as [`Parser::usage()`](Parser::usage).
 
+mod complete;
+mod conv;
+mod derive;
 mod err;
+mod group;
+mod man;
+mod matches;
 mod parser;
 mod prsrutil;
+mod respfile;
 mod spec;
+mod warn;
 
+pub use crate::complete::Shell;
+pub use crate::conv::{FromArg, FromArgs};
+pub use crate::derive::QArgs;
+pub use crate::group::Group;
+pub use crate::matches::{Match, Matches};
 pub use crate::parser::Parser;
 pub use crate::spec::{Builder, Nargs, Spec};
+pub use crate::warn::Warning;
+
+#[cfg(feature = "derive")]
+pub use qargparser_derive::QArgs;
 
-pub use crate::err::ErrKind;
+pub use crate::err::{
+  AmbigErr, ArgErr, ChoiceErr, ConvErr, ErrKind, GroupErr, MissOptErr, OptErr,
+  RespFileErr, VarErr
+};
 
 // vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
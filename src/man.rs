@@ -0,0 +1,172 @@
+//! Man page generation.
+
+use std::path::Path;
+
+use crate::parser::Parser;
+
+
+/// Escape text for use in roff.  Lines which would otherwise be taken for
+/// control lines are protected.
+fn escape(s: &str) -> String {
+  let s = s.replace('\\', "\\e");
+  s.lines()
+    .map(|l| {
+      if l.starts_with('.') || l.starts_with('\'') {
+        format!("\\&{}", l)
+      } else {
+        l.to_string()
+      }
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+/// Escape option strings and usage words for roff.  Dashes are rendered as
+/// real minus signs, so that they can be copied and pasted.
+fn escape_opt(s: &str) -> String {
+  escape(s).replace('-', "\\-")
+}
+
+/// Write a list of paragraphs.
+fn paragraphs(s: &mut String, ps: &[String]) {
+  for (i, p) in ps.iter().enumerate() {
+    if i > 0 {
+      s.push_str(".PP\n");
+    }
+    s.push_str(&escape(p));
+    s.push('\n');
+  }
+}
+
+/// Return the section name if the paragraph is a section heading, like
+/// "Examples:".
+fn heading(p: &str) -> Option<&str> {
+  let t = p.trim();
+  if t.ends_with(':') && !t.contains('\n') && t.len() < 40 {
+    Some(&t[..t.len() - 1])
+  } else {
+    None
+  }
+}
+
+
+impl<C> Parser<C> {
+  /// Write a roff man page for this parser to `out`.
+  ///
+  /// The page has the NAME, SYNOPSIS, DESCRIPTION, OPTIONS, ARGUMENTS and
+  /// COMMANDS sections, generated from the same data as
+  /// [`Parser::usage()`](Parser::usage).  The first paragraph of the top help
+  /// text is used as the short description in NAME, and all of the top help
+  /// text goes into DESCRIPTION.
+  ///
+  /// The bottom help text is used for additional sections.  A single line
+  /// paragraph ending with a colon, like "Examples:", starts a new section.
+  /// Any paragraphs before the first such heading are put in a NOTES
+  /// section.
+  pub fn man(&self, section: &str, out: &mut dyn std::io::Write) {
+    let name = match Path::new(&self.argv0).file_name() {
+      Some(name) => name.to_string_lossy().to_string(),
+      None => self.argv0.clone()
+    };
+    let mut s = String::new();
+
+    s.push_str(&format!(
+      ".TH \"{}\" \"{}\"\n",
+      escape(&name.to_uppercase().replace(' ', "-")),
+      escape(section)
+    ));
+
+    s.push_str(".SH NAME\n");
+    match self.tophelp.first() {
+      Some(p) => {
+        let desc = p.split_whitespace().collect::<Vec<&str>>().join(" ");
+        s.push_str(&format!("{} \\- {}\n", escape(&name), escape(&desc)));
+      }
+      None => {
+        s.push_str(&format!("{}\n", escape(&name)));
+      }
+    }
+
+    s.push_str(".SH SYNOPSIS\n");
+    s.push_str(&format!(".B {}\n", escape(&name)));
+    let words = self.get_usage_words();
+    if !words.is_empty() {
+      let words: Vec<String> = words.iter().map(|w| escape_opt(w)).collect();
+      s.push_str(&words.join(" "));
+      s.push('\n');
+    }
+
+    if !self.tophelp.is_empty() {
+      s.push_str(".SH DESCRIPTION\n");
+      paragraphs(&mut s, &self.tophelp);
+    }
+
+    let opts: Vec<_> = self
+      .get_opts()
+      .into_iter()
+      .filter(|spec| !spec.borrow().is_hidden())
+      .collect();
+    if !opts.is_empty() {
+      s.push_str(".SH OPTIONS\n");
+      for spec in &opts {
+        let spec = spec.borrow();
+        s.push_str(".TP\n");
+        s.push_str(&format!(
+          ".B {}\n",
+          escape_opt(&spec.get_opts_usage_str())
+        ));
+        paragraphs(&mut s, &spec.get_full_help_text());
+      }
+    }
+
+    let posargs: Vec<_> = self
+      .get_posargs()
+      .into_iter()
+      .filter(|spec| !spec.borrow().is_hidden())
+      .collect();
+    if !posargs.is_empty() {
+      s.push_str(".SH ARGUMENTS\n");
+      for spec in &posargs {
+        let spec = spec.borrow();
+        s.push_str(".TP\n");
+        s.push_str(&format!(
+          ".B {}\n",
+          escape_opt(&spec.get_help_title_str())
+        ));
+        paragraphs(&mut s, &spec.get_full_help_text());
+      }
+    }
+
+    if !self.subcmds.is_empty() {
+      s.push_str(".SH COMMANDS\n");
+      for sub in &self.subcmds {
+        s.push_str(".TP\n");
+        s.push_str(&format!(".B {}\n", escape(&sub.name)));
+        paragraphs(&mut s, &sub.help);
+      }
+    }
+
+    // Split the bottom help into sections.
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    for p in &self.bottomhelp {
+      if let Some(title) = heading(p) {
+        sections.push((title.to_uppercase(), Vec::new()));
+        continue;
+      }
+      if sections.is_empty() {
+        sections.push((String::from("NOTES"), Vec::new()));
+      }
+      if let Some((_, ps)) = sections.last_mut() {
+        ps.push(p.clone());
+      }
+    }
+    for (title, ps) in &sections {
+      s.push_str(&format!(".SH \"{}\"\n", escape(title)));
+      paragraphs(&mut s, ps);
+    }
+
+    out.write_all(s.as_bytes()).expect("Unable to write output.");
+  }
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,136 @@
+//! Record of the specs matched by a parser.
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+
+use crate::spec::Spec;
+
+
+/// What the parser saw of a single spec.
+#[derive(Clone, Debug, Default)]
+pub struct Match {
+  /// The number of times the spec was matched on the command line, or taken
+  /// from its environment variable.
+  pub count: usize,
+
+  /// All the spec's arguments, in the order they were seen.  If the spec
+  /// wasn't matched, this holds its default value, if any.
+  pub values: Vec<OsString>,
+
+  /// Indices of the matched options and positional arguments in the
+  /// parser's argument list.  Options bundled in a single argument, like
+  /// `-vf`, share its index.
+  pub indices: Vec<usize>,
+
+  /// Whether the last match of a [negatable](crate::Builder::negatable)
+  /// option was its negated form, like `--no-color`.
+  pub negated: bool
+}
+
+
+/// The specs matched by a parser, keyed by their names.
+///
+/// Specs are keyed by their [`name`](crate::Builder::name) if they have one,
+/// or by their long option, or by their short option.  Matches are recorded
+/// alongside calling the specs' handlers; to only record matches, build
+/// specs using [`Builder::build_match()`](crate::Builder::build_match).
+///
+/// Options and arguments which belong to a sub-command are recorded in the
+/// sub-command's parser.  Global options given after the sub-command are
+/// recorded both in the sub-command's parser and in the parsers it inherited
+/// them from.
+#[derive(Clone, Debug, Default)]
+pub struct Matches {
+  map: HashMap<String, Match>
+}
+
+impl Matches {
+  /// Get everything recorded for a spec, if it was matched or has a default
+  /// value.
+  pub fn get(&self, name: &str) -> Option<&Match> {
+    self.map.get(name)
+  }
+
+  /// Returns `true` if the spec was matched on the command line or taken
+  /// from its environment variable.
+  pub fn contains(&self, name: &str) -> bool {
+    self.count(name) > 0
+  }
+
+  /// Get the number of times a spec was matched.
+  pub fn count(&self, name: &str) -> usize {
+    self.map.get(name).map_or(0, |m| m.count)
+  }
+
+  /// Get the last argument of a spec.
+  pub fn value(&self, name: &str) -> Option<&OsStr> {
+    self.values(name).last().map(OsString::as_os_str)
+  }
+
+  /// Get all the arguments of a spec, in the order they were seen.
+  pub fn values(&self, name: &str) -> &[OsString] {
+    self.map.get(name).map_or(&[], |m| &m.values)
+  }
+
+  /// Get the indices of a spec's matches in the parser's argument list.
+  pub fn indices(&self, name: &str) -> &[usize] {
+    self.map.get(name).map_or(&[], |m| &m.indices)
+  }
+
+  /// Record a match of a spec.  `index` is `None` for specs which were not
+  /// matched on the command line, and `seen` is `false` for default values.
+  pub(crate) fn record<C>(
+    &mut self,
+    spec: &Spec<C>,
+    index: Option<usize>,
+    seen: bool,
+    args: &[OsString]
+  ) {
+    let key = match get_key(spec) {
+      Some(key) => key,
+      None => return
+    };
+
+    let m = self.map.entry(key).or_default();
+    if seen {
+      m.count += 1;
+      m.negated = spec.negated;
+    }
+    m.values.extend_from_slice(args);
+    m.indices.extend(index);
+  }
+
+  /// Add what `other` recorded for a spec to this record.
+  pub(crate) fn merge<C>(&mut self, other: &Matches, spec: &Spec<C>) {
+    let key = match get_key(spec) {
+      Some(key) => key,
+      None => return
+    };
+    let theirs = match other.map.get(&key) {
+      Some(theirs) => theirs,
+      None => return
+    };
+
+    let m = self.map.entry(key).or_default();
+    if theirs.count > 0 {
+      m.count += theirs.count;
+      m.negated = theirs.negated;
+    }
+    m.values.extend_from_slice(&theirs.values);
+    m.indices.extend_from_slice(&theirs.indices);
+  }
+}
+
+
+/// Get the key a spec's matches are recorded under.
+fn get_key<C>(spec: &Spec<C>) -> Option<String> {
+  if let Some(ref n) = spec.name {
+    Some(n.clone())
+  } else if let Some(ref o) = spec.lopt {
+    Some(o.clone())
+  } else {
+    spec.sopt.map(|c| c.to_string())
+  }
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -3,46 +3,103 @@ use std::rc::Rc;
 //use std::cell::{RefCell, Ref, RefMut};
 use std::cell::RefCell;
 use std::env;
+use std::ffi::{OsStr, OsString};
 
 use qpprint as pprint;
 
+use crate::conv::TypedErr;
+use crate::group::{Group, OptGroup};
+use crate::matches::Matches;
 use crate::prsrutil;
-use crate::spec::Spec;
+use crate::respfile;
+use crate::spec::{Nargs, Proc, Spec};
+use crate::warn::Warning;
 
-use crate::err::{ErrKind, SpecErr};
+use crate::err::{
+  AmbigErr, ArgErr, ChoiceErr, ConvErr, ErrKind, MissOptErr, OptErr, SpecErr,
+  VarErr
+};
+
+
+pub(crate) type SpecRc<C> = Rc<RefCell<Spec<C>>>;
+
+type WarningSink = Box<dyn FnMut(&Warning)>;
+
+
+/// A named sub-command parser registered on a parent parser.
+pub(crate) struct Subcmd<C> {
+  pub(crate) name: String,
+  pub(crate) help: Vec<String>,
+  pub(crate) prsr: Parser<C>
+}
 
 
 /// The core parser.
 pub struct Parser<C> {
   ctx: C,
-  specs: Vec<Rc<RefCell<Spec<C>>>>,
-  sopts: HashMap<char, Rc<RefCell<Spec<C>>>>,
-  lopts: HashMap<String, Rc<RefCell<Spec<C>>>>,
-  named: HashMap<String, Rc<RefCell<Spec<C>>>>,
-  posargs: Vec<Rc<RefCell<Spec<C>>>>,
-  argv0: String,
-  args: Vec<String>,
+  pub(crate) specs: Vec<SpecRc<C>>,
+  sopts: HashMap<char, SpecRc<C>>,
+  lopts: HashMap<String, SpecRc<C>>,
+  named: HashMap<String, SpecRc<C>>,
+  pub(crate) posargs: Vec<SpecRc<C>>,
+  pub(crate) argv0: String,
+  args: Vec<OsString>,
+  /// Index of each argument in the original argument list, which differs
+  /// from its position in `args` once arguments have been split.
+  argidx: Vec<usize>,
   curarg: usize,
   posplit: bool,
   posarg: usize,
+  /// Index of an argument which was attached to an option, as in
+  /// `--color=always` or `-calways`.
+  attached: Option<usize>,
   err: Option<ErrKind<C>>,
-  tophelp: Vec<String>,
-  bottomhelp: Vec<String>
+  pub(crate) tophelp: Vec<String>,
+  pub(crate) bottomhelp: Vec<String>,
+  pub(crate) subcmds: Vec<Subcmd<C>>,
+  subcmd: Option<usize>,
+  /// Global options inherited from the parent parser, which aren't shadowed
+  /// by this parser's own options.
+  inherited: Vec<SpecRc<C>>,
+  groups: Vec<OptGroup<C>>,
+  matches: Matches,
+  prefix_match: bool,
+  respfiles: bool,
+  /// Set once response files have been expanded.
+  expanded: bool,
+  /// The environment variable the first arguments were taken from, and the
+  /// number of arguments taken from it.
+  prefix_var: Option<(String, usize)>,
+  warnings: Vec<Warning>,
+  warning_sink: Option<WarningSink>,
+  passthrough: bool,
+  /// Unknown options, and their arguments, which were passed through.
+  unrecognized: Vec<OsString>
 }
 
 impl<C> Parser<C> {
   /// Create a parser for parsing the process' command line arguments.
+  ///
+  /// Arguments are kept as `OsString`s, so non-UTF-8 arguments will not cause
+  /// a panic.  The program name is converted to UTF-8 lossily.
   pub fn from_env(ctx: C) -> Self {
-    let args: Vec<String> = env::args().collect();
-    let args2 = &args[1..];
+    let mut args = env::args_os();
+    let argv0 = args
+      .next()
+      .map(|a| a.to_string_lossy().into_owned())
+      .unwrap_or_default();
 
-    Parser::from_args(&args[0], &args2.to_vec(), ctx)
+    Parser::from_args(&argv0, args, ctx)
   }
 
+  /// Create a parser for parsing an explicit list of arguments.
+  ///
+  /// The arguments can be anything that can be referenced as an `OsStr`,
+  /// which includes `&str`, `String`, `OsString` and `PathBuf`.
   pub fn from_args<I, S>(argv0: &str, args: I, ctx: C) -> Self
   where
     I: IntoIterator<Item = S>,
-    S: AsRef<str>
+    S: AsRef<OsStr>
   {
     let specs = Vec::new();
     let sopts = HashMap::new();
@@ -51,7 +108,7 @@ impl<C> Parser<C> {
     let named = HashMap::new();
     let new_args = args
       .into_iter()
-      .map(|x| String::from(x.as_ref()))
+      .map(|x| x.as_ref().to_os_string())
       .collect::<Vec<_>>();
 
     Parser {
@@ -61,33 +118,152 @@ impl<C> Parser<C> {
       posargs,
       named,
       argv0: String::from(argv0),
+      argidx: (0..new_args.len()).collect(),
       args: new_args,
       ctx,
       curarg: 0,
       posplit: false,
       posarg: 0,
+      attached: None,
       err: None,
       tophelp: Vec::new(),
-      bottomhelp: Vec::new()
+      bottomhelp: Vec::new(),
+      subcmds: Vec::new(),
+      subcmd: None,
+      inherited: Vec::new(),
+      groups: Vec::new(),
+      matches: Matches::default(),
+      prefix_match: false,
+      respfiles: false,
+      expanded: false,
+      prefix_var: None,
+      warnings: Vec::new(),
+      warning_sink: None,
+      passthrough: false,
+      unrecognized: Vec::new()
     }
   }
 
+  /// Create a parser for parsing the process' command line arguments,
+  /// preceded by the arguments in the environment variable `var`.
+  ///
+  /// This mirrors tools like `less`, which read default options from an
+  /// environment variable like `LESS="-R -i"`.  The variable is split into
+  /// arguments like a shell would, honoring quotes and backslash escapes;
+  /// values which aren't valid UTF-8 are passed on as they are.  Since they
+  /// come first, arguments on the command line override those in the
+  /// variable.
+  ///
+  /// Errors caused by arguments from the variable are reported as
+  /// [`ErrKind::PrefixVar`], wrapping the original error.
+  pub fn from_env_with_prefix_var(
+    ctx: C,
+    var: &str
+  ) -> Result<Self, ErrKind<C>> {
+    let mut args = env::args_os();
+    let argv0 = args
+      .next()
+      .map(|a| a.to_string_lossy().into_owned())
+      .unwrap_or_default();
+
+    Parser::from_args_with_prefix_var(&argv0, args, ctx, var)
+  }
+
+  /// Create a parser for parsing an explicit list of arguments, preceded by
+  /// the arguments in the environment variable `var`.
+  ///
+  /// See [`Parser::from_env_with_prefix_var()`](
+  /// Parser::from_env_with_prefix_var).
+  pub fn from_args_with_prefix_var<I, S>(
+    argv0: &str,
+    args: I,
+    ctx: C,
+    var: &str
+  ) -> Result<Self, ErrKind<C>>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>
+  {
+    let mkerr = |msg: &str| {
+      ErrKind::PrefixVar(VarErr {
+        var: var.to_string(),
+        msg: msg.to_string(),
+        err: None
+      })
+    };
+
+    let prefix = match env::var_os(var) {
+      Some(val) => prsrutil::split_words(&val).map_err(|e| mkerr(&e))?,
+      None => Vec::new()
+    };
+    let n = prefix.len();
+
+    let args = prefix
+      .into_iter()
+      .chain(args.into_iter().map(|x| x.as_ref().to_os_string()));
+    let mut prsr = Parser::from_args(argv0, args, ctx);
+    prsr.prefix_var = Some((var.to_string(), n));
+
+    Ok(prsr)
+  }
+
   pub fn add(&mut self, spec: Spec<C>) -> Result<(), ErrKind<C>> {
     //let aspec_rc: Rc<RefCell<ArgSpec>> = Rc::new(RefCell::new(argspec));
     let aspec_rc = Rc::new(RefCell::new(spec));
     let asp = aspec_rc.borrow();
 
+    //
+    // Make sure this spec is sane
+    //
+    if let Nargs::Range(min, max) = asp.nargs() {
+      if max < min || max == 0 {
+        let errstr = format!("Invalid argument range {}..{}.", min, max);
+        return Err(ErrKind::BadContext(errstr));
+      }
+    }
+    if asp.is_negatable() && (asp.lopt.is_none() || asp.takes_args()) {
+      return Err(ErrKind::BadContext(
+        "Negatable option must have a long option and take no arguments."
+          .to_string()
+      ));
+    }
+    if asp.has_aliases() && asp.sopt.is_none() && asp.lopt.is_none() {
+      return Err(ErrKind::BadContext(
+        "Option aliases require a short or long option.".to_string()
+      ));
+    }
+    if asp.is_pos() {
+      if !asp.takes_args() {
+        return Err(ErrKind::BadContext(
+          "Positional argument spec must take at least one argument."
+            .to_string()
+        ));
+      }
+
+      // Make sure the last positional argument spec doesn't capture "the
+      // rest".
+      if self.have_capture_rest() {
+        return Err(ErrKind::BadContext(
+          "Can't add positional argument after
+existing 'capture all' argument"
+            .to_string()
+        ));
+      }
+    }
+
     //
     // Make sure this spec is unique
     //
-    if let Some(ref c) = asp.sopt {
-      if self.sopts.contains_key(c) {
+    let sopts = asp.sopt_names(true);
+    for (i, c) in sopts.iter().enumerate() {
+      if self.sopts.contains_key(c) || sopts[..i].contains(c) {
         let errstr = format!("The short option '{}' already in use.", c);
         return Err(ErrKind::Collision(errstr));
       }
     }
-    if let Some(ref o) = asp.lopt {
-      if self.lopts.contains_key(o) {
+    let lopts = asp.lopt_names(true, true);
+    for (i, o) in lopts.iter().enumerate() {
+      if self.lopts.contains_key(o) || lopts[..i].contains(o) {
         let errstr = format!("The long option '{}' already in use.", o);
         return Err(ErrKind::Collision(errstr));
       }
@@ -107,12 +283,12 @@ impl<C> Parser<C> {
     // Add to list of all specs
     self.specs.push(Rc::clone(&aspec_rc));
 
-    if let Some(ref c) = asp.sopt {
-      self.sopts.insert(*c, Rc::clone(&aspec_rc));
+    for c in sopts {
+      self.sopts.insert(c, Rc::clone(&aspec_rc));
     }
 
-    if let Some(ref o) = asp.lopt {
-      self.lopts.insert(o.clone(), Rc::clone(&aspec_rc));
+    for o in lopts {
+      self.lopts.insert(o, Rc::clone(&aspec_rc));
     }
 
     if let Some(ref n) = asp.name {
@@ -120,40 +296,224 @@ impl<C> Parser<C> {
     }
 
     // If it's neither a long or short option then it's a positional argument.
-    if asp.is_pos() == true {
-      // Make sure the last positional argument spec doesn't capture "the
-      // rest".
-      if self.have_capture_rest() {
-        return Err(ErrKind::BadContext(
-          "Can't add positional argument after
-existing 'capture all' argument"
-            .to_string()
-        ));
-      }
+    if asp.is_pos() {
       self.posargs.push(Rc::clone(&aspec_rc));
     }
     Ok(())
   }
 
-  pub fn get_arg0(&self) -> &str {
-    &self.argv0
+
+  /// Register a sub-command parser.
+  ///
+  /// Once all of this parser's positional arguments have been processed, the
+  /// next positional argument is taken to be a sub-command name.  The
+  /// remaining arguments are then handed over to the sub-command's parser,
+  /// which populates this parser's context.  Options marked as
+  /// [`global`](crate::Builder::global) are inherited by the sub-command
+  /// parser, which lists them in its help once it has been dispatched to.
+  /// Arguments after a `--` given before the sub-command name are not taken
+  /// to be options by the sub-command parser either.
+  ///
+  /// The sub-command parser's own context is only a placeholder; see
+  /// [`Parser::new_subcmd()`](Parser::new_subcmd).
+  pub fn add_subcmd<I, S>(
+    &mut self,
+    name: &str,
+    help: I,
+    mut prsr: Parser<C>
+  ) -> Result<(), ErrKind<C>>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>
+  {
+    if self.subcmds.iter().any(|x| x.name == name) {
+      let errstr = format!("The command '{}' already in use.", name);
+      return Err(ErrKind::Collision(errstr));
+    }
+
+    prsr.set_argv0(format!("{} {}", self.argv0, name));
+
+    self.subcmds.push(Subcmd {
+      name: name.to_string(),
+      help: help.into_iter().map(|x| String::from(x.as_ref())).collect(),
+      prsr
+    });
+
+    Ok(())
   }
 
-  pub fn get_remaining_args(&mut self) -> Vec<String> {
-    let mut remain = Vec::new();
+  /// Set the program name, and the names of the nested sub-command parsers
+  /// which are derived from it.
+  fn set_argv0(&mut self, argv0: String) {
+    for subcmd in &mut self.subcmds {
+      subcmd.prsr.set_argv0(format!("{} {}", argv0, subcmd.name));
+    }
+    self.argv0 = argv0;
+  }
+
+  /// Register a group of options which have already been added to the
+  /// parser.  Options are referred to as they appear on the command line, like
+  /// `"--json"` or `"-j"`.
+  ///
+  /// The group's constraint is checked by
+  /// [`Parser::validate()`](Parser::validate), which reports violations as
+  /// [`ErrKind::Group`].
+  pub fn add_group(
+    &mut self,
+    kind: Group,
+    opts: &[&str]
+  ) -> Result<(), ErrKind<C>> {
+    if opts.len() < 2 {
+      return Err(ErrKind::BadContext(
+        "An option group needs at least two options.".to_string()
+      ));
+    }
 
-    for i in self.curarg..self.args.len() {
-      remain.push(self.args[i].to_string());
+    let mut specs = Vec::new();
+    for opt in opts {
+      let spec = if let Some(lopt) = opt.strip_prefix("--") {
+        self.lopts.get(lopt)
+      } else if let Some(sopt) = opt.strip_prefix('-') {
+        let mut chars = sopt.chars();
+        match (chars.next(), chars.next()) {
+          (Some(c), None) => self.sopts.get(&c),
+          _ => None
+        }
+      } else {
+        None
+      };
+      match spec {
+        Some(spec) => specs.push(Rc::clone(spec)),
+        None => {
+          let errstr = format!("Unknown option '{}' in group.", opt);
+          return Err(ErrKind::MissSpec(errstr));
+        }
+      }
     }
 
-    // ToDo
-    // let remain: Vec<String> = self.args.iter().skip(self.curarg).collect();
+    self.groups.push(OptGroup { kind, specs });
+
+    Ok(())
+  }
+
+  /// Enable or disable matching long options by unique prefix.
+  ///
+  /// When enabled, a long option which isn't registered is taken to be an
+  /// abbreviation of the registered long option it is a prefix of, like
+  /// `--verb` for `--verbose`.  If the prefix matches more than one option,
+  /// the parser fails with [`ErrKind::Ambiguous`].  Hidden options must
+  /// always be spelled out.
+  pub fn set_prefix_matching(&mut self, enable: bool) {
+    self.prefix_match = enable;
+  }
+
+  /// Enable or disable response file expansion.
+  ///
+  /// When enabled, arguments of the form `@path` are replaced by the
+  /// arguments in the file at `path` before any argument is processed.  The
+  /// file's contents are split into arguments like a shell would, honoring
+  /// quotes and backslash escapes, and may refer to other response files.
+  /// Relative paths in a response file are relative to the directory of that
+  /// file, rather than the current directory.  Use `@@` to pass an argument
+  /// starting with a literal `@`.  Arguments following `--` are not
+  /// expanded.
+  ///
+  /// Files which can't be read or parsed, or which include themselves, are
+  /// reported as [`ErrKind::RespFile`].
+  pub fn set_response_files(&mut self, enable: bool) {
+    self.respfiles = enable;
+  }
+
+  /// Enable or disable passing unknown options through.
+  ///
+  /// When enabled, unknown options don't cause
+  /// [`ErrKind::UnknownOpt`]; they are collected, in order, for
+  /// [`Parser::get_unrecognized()`](Parser::get_unrecognized) instead.  This
+  /// is useful for wrapper tools which forward the options they don't know
+  /// to a child process.
+  ///
+  /// Since the parser can't know which arguments an unknown option takes, an
+  /// argument attached to it, like in `--foo=bar`, stays attached.
+  /// Otherwise the arguments which follow it, up to the next option-like
+  /// argument, are passed through along with it, unless there are positional
+  /// argument specs or sub-commands left to take them.  An unknown short
+  /// option bundled after known ones, like `x` in `-vxf`, is passed through
+  /// along with the rest of the bundle, `-xf`.
+  pub fn set_passthrough(&mut self, enable: bool) {
+    self.passthrough = enable;
+  }
 
-    remain
+  /// Get the unknown options, and their arguments, which were passed
+  /// through, as they appeared on the command line.
+  ///
+  /// See [`Parser::set_passthrough()`](Parser::set_passthrough).
+  pub fn get_unrecognized(&self) -> &[OsString] {
+    &self.unrecognized
+  }
+
+  /// Get the name of the sub-command which was selected by the parser, if
+  /// any.
+  pub fn get_subcmd(&self) -> Option<&str> {
+    self.subcmd.map(|i| self.subcmds[i].name.as_str())
+  }
+
+  /// Get the parser of the sub-command which was selected by the parser, if
+  /// any.
+  ///
+  /// This is useful for showing the sub-command's help text.
+  pub fn get_subcmd_parser(&self) -> Option<&Parser<C>> {
+    self.subcmd.map(|i| &self.subcmds[i].prsr)
+  }
+
+  /// Get the specs matched so far, keyed by name.
+  ///
+  /// This is populated as arguments are processed, so it is complete once
+  /// [`Parser::parse()`](Parser::parse) has returned successfully.
+  pub fn get_matches(&self) -> &Matches {
+    &self.matches
+  }
+
+  /// Get the warnings about deprecated options used so far, in the order
+  /// the options appeared.  Warnings routed to a sink are not included.
+  pub fn get_warnings(&self) -> &[Warning] {
+    &self.warnings
+  }
+
+  /// Route warnings about deprecated options to `sink`, like a logger,
+  /// instead of recording them.  The sink is also used for sub-commands.
+  pub fn set_warning_sink<F>(&mut self, sink: F)
+  where
+    F: FnMut(&Warning) + 'static
+  {
+    self.warning_sink = Some(Box::new(sink));
+  }
+
+  pub fn get_arg0(&self) -> &str {
+    &self.argv0
+  }
+
+  /// Get the arguments which have not been processed yet.
+  ///
+  /// Arguments which are not valid UTF-8 are converted lossily.  Use
+  /// [`Parser::get_remaining_args_os()`](Parser::get_remaining_args_os) to
+  /// get the arguments unaltered.
+  pub fn get_remaining_args(&mut self) -> Vec<String> {
+    self
+      .args
+      .iter()
+      .skip(self.curarg)
+      .map(|a| a.to_string_lossy().into_owned())
+      .collect()
+  }
+
+  /// Get the arguments which have not been processed yet, as they were
+  /// passed to the parser.
+  pub fn get_remaining_args_os(&mut self) -> Vec<OsString> {
+    self.args.iter().skip(self.curarg).cloned().collect()
   }
 
   pub fn have_capture_rest(&self) -> bool {
-    if let Some(ref spec) = &self.posargs.last() {
+    if let Some(spec) = &self.posargs.last() {
       let spec = spec.borrow();
       if spec.is_capture_rest() {
         return true;
@@ -200,90 +560,271 @@ existing 'capture all' argument"
   }
 
 
-  pub fn parse(&mut self) -> Result<Option<Rc<RefCell<Spec<C>>>>, ErrKind<C>> {
-    /*
-        for n in self {
-          let spec = n.borrow();
-          if spec.exit == true {
-            return Ok(Some(Rc::clone(&n)));
-          }
-        }
-    */
-    while let Ok(Some(n)) = self.next() {
+  /// Parse all the arguments.
+  ///
+  /// Returns the spec which caused the parser to exit early, if any.  The
+  /// first error returned by [`Parser::next()`](Parser::next) is returned
+  /// as-is, and no further arguments are processed.  Once all arguments have
+  /// been processed the parser is validated.
+  pub fn parse(&mut self) -> Result<Option<SpecRc<C>>, ErrKind<C>> {
+    while let Some(n) = self.next()? {
       let spec = n.borrow();
-      if spec.exit == true {
+      if spec.exit {
         return Ok(Some(Rc::clone(&n)));
       }
     }
 
+    self.apply_defaults()?;
     self.validate()?;
 
     Ok(None)
   }
 
 
-  pub fn next(&mut self) -> Result<Option<Rc<RefCell<Spec<C>>>>, ErrKind<C>> {
-    if self.curarg == self.args.len() {
-      return Ok(None);
+  #[allow(clippy::should_implement_trait)]
+  pub fn next(&mut self) -> Result<Option<SpecRc<C>>, ErrKind<C>> {
+    if self.respfiles && !self.expanded {
+      self.expand_respfiles()?;
     }
 
-    if self.args[self.curarg] == "--" {
-      self.posplit = true;
-      self.curarg = self.curarg + 1;
+    // Unknown options which are passed through are skipped.
+    let (index, spec, args) = loop {
       if self.curarg == self.args.len() {
         return Ok(None);
       }
-    }
 
-    let ret: Option<Rc<RefCell<Spec<C>>>>;
-    let mut args: Vec<String> = Vec::new();
-
-    if !self.posplit && prsrutil::maybe_lopt(&self.args[self.curarg]) {
-      match self.proc_lopt(&mut args) {
-        Ok(spec) => ret = Some(spec),
-        Err(err) => return Err(err)
-      }
-    } else if !self.posplit && prsrutil::maybe_sopt(&self.args[self.curarg]) {
-      match self.proc_sopt(&mut args) {
-        Ok(spec) => ret = Some(spec),
-        Err(err) => return Err(err)
+      if self.args[self.curarg] == "--" {
+        self.posplit = true;
+        self.curarg += 1;
+        if self.curarg == self.args.len() {
+          return Ok(None);
+        }
       }
-    } else {
-      match self.proc_posarg(&mut args) {
-        Ok(spec) => ret = Some(spec),
-        Err(err) => return Err(err)
+
+      let mut args: Vec<OsString> = Vec::new();
+      let index = self.argidx[self.curarg];
+
+      let spec = if !self.posplit
+        && prsrutil::maybe_lopt(&self.args[self.curarg])
+      {
+        self.proc_lopt(&mut args)
+      } else if !self.posplit && prsrutil::maybe_sopt(&self.args[self.curarg])
+      {
+        self.proc_sopt(&mut args)
+      } else if !self.subcmds.is_empty() && self.posarg == self.posargs.len()
+      {
+        return self.proc_subcmd();
+      } else {
+        self.proc_posarg(&mut args)
+      };
+      if self.passthrough && matches!(spec, Err(ErrKind::UnknownOpt(_))) {
+        self.pass_unknown();
+        continue;
       }
+      break (index, spec, args);
+    };
+    let spec = spec.map_err(|err| self.attribute(index, err))?;
+
+    spec.borrow_mut().seen += 1;
+    self.matches.record(&spec.borrow(), Some(index), true, &args);
+    self.call_proc(&spec, args)
+      .map_err(|err| self.attribute(index, err))?;
+
+    self.curarg += 1;
+
+    Ok(Some(spec))
+  }
+
+
+  /// Pass the unknown option at the current argument through, along with the
+  /// arguments it may take.
+  fn pass_unknown(&mut self) {
+    let mut opt = self.args[self.curarg].clone();
+    self.curarg += 1;
+
+    // Undo splitting ["--foo=bar"] into ["--foo", "bar"].
+    if self.attached == Some(self.curarg) {
+      self.attached = None;
+      opt.push("=");
+      opt.push(&self.args[self.curarg]);
+      self.unrecognized.push(opt);
+      self.curarg += 1;
+      return;
     }
+    self.unrecognized.push(opt);
+
+    if self.posarg < self.posargs.len() || !self.subcmds.is_empty() {
+      return;
+    }
+    while self.curarg < self.args.len()
+      && !self.is_optlike(&self.args[self.curarg])
+    {
+      self.unrecognized.push(self.args[self.curarg].clone());
+      self.curarg += 1;
+    }
+  }
 
-    if let Some(ref spec) = ret {
-      let spec = spec.borrow();
 
-      // Call the argspec's callback function
-      (spec.proc)(&*spec, &mut self.ctx, &args);
+  /// Attribute an error caused by the argument at `index` to the prefix
+  /// variable, if the argument came from it.
+  fn attribute(&self, index: usize, err: ErrKind<C>) -> ErrKind<C> {
+    match self.prefix_var {
+      Some((ref var, n)) if index < n => ErrKind::PrefixVar(VarErr {
+        var: var.clone(),
+        msg: err.to_string(),
+        err: Some(Box::new(err))
+      }),
+      _ => err
     }
+  }
 
-    self.curarg += 1;
 
-    return Ok(ret);
+  /// Expand response files among the arguments which have not been processed
+  /// yet.
+  fn expand_respfiles(&mut self) -> Result<(), ErrKind<C>> {
+    let first = self.curarg;
+    let (args, argidx) =
+      respfile::expand(&self.args[first..], &self.argidx[first..])
+        .map_err(ErrKind::RespFile)?;
+
+    self.args.truncate(first);
+    self.args.extend(args);
+    self.argidx.truncate(first);
+    self.argidx.extend(argidx);
+    self.expanded = true;
+
+    Ok(())
+  }
+
+
+  /// Call the handlers of specs which were not matched, but which have an
+  /// environment variable set or a default value.
+  fn apply_defaults(&mut self) -> Result<(), ErrKind<C>> {
+    let specs: Vec<SpecRc<C>> = self
+      .specs
+      .iter()
+      .filter(|spec| {
+        let spec = spec.borrow();
+        spec.seen == 0 && (spec.env.is_some() || spec.default.is_some())
+      })
+      .map(Rc::clone)
+      .collect();
+
+    for spec in &specs {
+      if let Some(args) = get_env_args(spec)? {
+        check_choices(spec, &args)?;
+        spec.borrow_mut().seen += 1;
+        self.matches.record(&spec.borrow(), None, true, &args);
+        self.call_proc(spec, args)?;
+        continue;
+      }
+
+      let args: Vec<OsString> = match spec.borrow().default {
+        Some(ref default) => default.iter().map(OsString::from).collect(),
+        None => continue
+      };
+      check_choices(spec, &args)?;
+      self.matches.record(&spec.borrow(), None, false, &args);
+      self.call_proc(spec, args)?;
+    }
+
+    Ok(())
+  }
+
+
+  /// Call the argspec's callback function, converting the arguments to UTF-8
+  /// if the handler expects `String`s.
+  fn call_proc(
+    &mut self,
+    spec_rc: &SpecRc<C>,
+    args: Vec<OsString>
+  ) -> Result<(), ErrKind<C>> {
+    let spec = spec_rc.borrow();
+    let res = match &spec.proc {
+      Proc::Str(proc) => {
+        let sargs = to_strings(spec_rc, args)?;
+        proc(&spec, &mut self.ctx, &sargs);
+        Ok(())
+      }
+      Proc::OsStr(proc) => {
+        proc(&spec, &mut self.ctx, &args);
+        Ok(())
+      }
+      Proc::TryStr(proc) => {
+        let sargs = to_strings(spec_rc, args)?;
+        proc(&spec, &mut self.ctx, &sargs).map_err(|msg| {
+          (sargs.into_iter().map(OsString::from).collect(), msg)
+        })
+      }
+      Proc::TryOsStr(proc) => {
+        proc(&spec, &mut self.ctx, &args).map_err(|msg| (args, msg))
+      }
+      Proc::Fn(proc) => {
+        let sargs = to_strings(spec_rc, args)?;
+        let mut proc = proc.borrow_mut();
+        proc(&spec, &mut self.ctx, &sargs).map_err(|msg| {
+          (sargs.into_iter().map(OsString::from).collect(), msg)
+        })
+      }
+      Proc::FnOs(proc) => {
+        let mut proc = proc.borrow_mut();
+        proc(&spec, &mut self.ctx, &args).map_err(|msg| (args, msg))
+      }
+      Proc::Nop => Ok(()),
+      Proc::Typed(proc) => {
+        let mut proc = proc.borrow_mut();
+        match proc.call(&spec, &mut self.ctx, &args) {
+          Ok(()) => Ok(()),
+          Err(TypedErr::Handler(msg)) => Err((args, msg)),
+          Err(TypedErr::Conv(index, msg)) => {
+            return Err(ErrKind::Conv(ConvErr {
+              spec: Rc::clone(spec_rc),
+              arg: args.get(index).cloned(),
+              index,
+              msg
+            }));
+          }
+        }
+      }
+    };
+
+    res.map_err(|(args, msg)| {
+      ErrKind::BadArg(ArgErr {
+        spec: Rc::clone(spec_rc),
+        args,
+        msg
+      })
+    })
   }
 
 
   fn proc_sopt(
     &mut self,
-    args: &mut Vec<String>
-  ) -> Result<Rc<RefCell<Spec<C>>>, ErrKind<C>> {
-    let spec_ref: Rc<RefCell<Spec<C>>>;
+    args: &mut Vec<OsString>
+  ) -> Result<SpecRc<C>, ErrKind<C>> {
+    let spec_ref: SpecRc<C>;
 
     // ["-vfbar"] -> ["-v", "-f", "bar"]
-    prsrutil::split_sopts_arg(&mut self.args, self.curarg, &self.sopts);
+    let len = self.args.len();
+    match prsrutil::split_sopts_arg(
+      &mut self.args,
+      self.curarg,
+      &self.sopts,
+      self.passthrough
+    ) {
+      Ok(Some(idx)) => self.attached = Some(idx),
+      Ok(None) => {}
+      Err(opt) => return Err(self.unknown_sopt(&opt))
+    }
+    self.split_argidx(len);
 
-    // This is excessive -- should probably be getting the nth() character
-    // instead, but this leads to having to deal with Option<> instead.
-    let sopt: Vec<char> = self.args[self.curarg].chars().collect();
-    let sopt = &sopt[1];
-    let spec = self.sopts.get(sopt);
-    if let Some(spec) = spec {
+    let sopt = self.args[self.curarg].as_encoded_bytes();
+    let sopt = prsrutil::decode_char(&sopt[1..]).map(|(c, _)| c);
+    let spec = sopt.and_then(|c| self.sopts.get(&c).map(|s| (c, s)));
+    if let Some((c, spec)) = spec {
       spec_ref = Rc::clone(spec);
+      spec_ref.borrow_mut().negated = false;
+      let index = self.argidx[self.curarg];
 
       //self.curarg += 1;
       match self.copyout_args(&spec_ref, args) {
@@ -292,28 +833,78 @@ existing 'capture all' argument"
           return Err(err);
         }
       }
+
+      let msg = spec_ref.borrow().sopt_deprecation(c).map(String::from);
+      if let Some(msg) = msg {
+        self.warn(format!("-{}", c), index, msg);
+      }
     } else {
-      let errstr = format!("Unknown short option '{}'", sopt);
-      return Err(ErrKind::UnknownOpt(errstr));
+      let opt = self.args[self.curarg].clone();
+      return Err(self.unknown_sopt(&opt));
     }
 
     Ok(spec_ref)
   }
 
 
+  /// Report the use of the deprecated option or positional argument `opt`
+  /// at `index`.
+  fn warn(&mut self, opt: String, index: usize, msg: String) {
+    let warning = Warning { opt, index, msg };
+    match self.warning_sink {
+      Some(ref mut sink) => sink(&warning),
+      None => self.warnings.push(warning)
+    }
+  }
+
+
+  /// Generate an error for an unknown short option, like `-x`.
+  fn unknown_sopt(&self, opt: &OsStr) -> ErrKind<C> {
+    let opt = opt.to_string_lossy().into_owned();
+    let mut chars = opt.chars().skip(1);
+    let suggestion = match (chars.next(), chars.next()) {
+      (Some(c), None) => self.suggest_sopt(c),
+      _ => None
+    };
+    let msg = format!("Unknown short option '{}'", &opt[1..]);
+    ErrKind::UnknownOpt(OptErr {
+      opt,
+      suggestion,
+      msg
+    })
+  }
+
+
   fn proc_lopt(
     &mut self,
-    args: &mut Vec<String>
-  ) -> Result<Rc<RefCell<Spec<C>>>, ErrKind<C>> {
-    let spec_ref: Rc<RefCell<Spec<C>>>;
+    args: &mut Vec<OsString>
+  ) -> Result<SpecRc<C>, ErrKind<C>> {
+    let spec_ref: SpecRc<C>;
 
     // ["--foo=bar"] -> ["--foo", "bar"]
-    prsrutil::split_lopt(&mut self.args, self.curarg);
+    if prsrutil::split_lopt(&mut self.args, self.curarg) {
+      self.attached = Some(self.curarg + 1);
+      self.split_argidx(self.args.len() - 1);
+    }
 
-    let lopt = &self.args[self.curarg][2..];
+    let arg = self.args[self.curarg].to_string_lossy();
+    let mut lopt = arg[2..].to_string();
+    if self.prefix_match && !self.lopts.contains_key(&lopt) {
+      if let Some(full) = self.match_lopt_prefix(&lopt)? {
+        lopt = full;
+      }
+    }
+    let lopt = lopt.as_str();
     let spec = self.lopts.get(lopt);
     if let Some(spec) = spec {
       spec_ref = Rc::clone(spec);
+      let negated = {
+        let spec = spec_ref.borrow();
+        spec.is_negatable()
+          && !spec.lopt_names(true, false).iter().any(|o| o == lopt)
+      };
+      spec_ref.borrow_mut().negated = negated;
+      let index = self.argidx[self.curarg];
 
       //self.curarg += 1;
       match self.copyout_args(&spec_ref, args) {
@@ -322,59 +913,231 @@ existing 'capture all' argument"
           return Err(err);
         }
       }
+
+      let msg = spec_ref.borrow().lopt_deprecation(lopt).map(String::from);
+      if let Some(msg) = msg {
+        self.warn(format!("--{}", lopt), index, msg);
+      }
     } else {
-      let errstr = format!("Unknown long option '{}'", lopt);
-      return Err(ErrKind::UnknownOpt(errstr));
+      let msg = format!("Unknown long option '{}'", lopt);
+      return Err(ErrKind::UnknownOpt(OptErr {
+        opt: format!("--{}", lopt),
+        suggestion: self.suggest_lopt(lopt),
+        msg
+      }));
     }
 
     Ok(spec_ref)
   }
 
 
+  /// Find the long option which starts with `prefix`.  Hidden options and
+  /// aliases are not considered.  It's an error if the prefix matches
+  /// options of more than one spec.
+  fn match_lopt_prefix(
+    &self,
+    prefix: &str
+  ) -> Result<Option<String>, ErrKind<C>> {
+    let mut matches: Vec<(&String, &SpecRc<C>)> = self
+      .lopts
+      .iter()
+      .filter(|(name, spec)| {
+        name.starts_with(prefix) && spec.borrow().is_visible_lopt(name)
+      })
+      .collect();
+    matches.sort_by(|a, b| a.0.cmp(b.0));
+
+    let (name, spec) = match matches.first() {
+      Some(m) => *m,
+      None => return Ok(None)
+    };
+    if matches.iter().all(|(_, s)| Rc::ptr_eq(s, spec)) {
+      return Ok(Some(name.clone()));
+    }
+
+    Err(ErrKind::Ambiguous(AmbigErr {
+      opt: format!("--{}", prefix),
+      candidates: matches.iter().map(|(n, _)| format!("--{}", n)).collect()
+    }))
+  }
+
+
+  /// Suggest a visible short option for a mistyped one.  Only options which
+  /// differ by case are suggested, like `-v` for `-V`.
+  fn suggest_sopt(&self, sopt: char) -> Option<String> {
+    let mut alts = sopt.to_lowercase().chain(sopt.to_uppercase());
+    alts.find_map(|c| match self.sopts.get(&c) {
+      Some(spec) if c != sopt && spec.borrow().is_visible_sopt(c) => {
+        Some(format!("-{}", c))
+      }
+      _ => None
+    })
+  }
+
+  /// Suggest a visible long option for a mistyped one, based on edit
+  /// distance.  A single character long option, like `--v`, may also be a
+  /// short option given with too many dashes.
+  fn suggest_lopt(&self, lopt: &str) -> Option<String> {
+    let mut chars = lopt.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+      if let Some(spec) = self.sopts.get(&c) {
+        if spec.borrow().is_visible_sopt(c) {
+          return Some(format!("-{}", c));
+        }
+      }
+    }
+
+    let candidates = self
+      .lopts
+      .iter()
+      .filter(|(name, spec)| spec.borrow().is_visible_lopt(name))
+      .map(|(name, _)| name.as_str());
+    prsrutil::closest(lopt, candidates).map(|name| format!("--{}", name))
+  }
+
+
   fn proc_posarg(
     &mut self,
-    args: &mut Vec<String>
-  ) -> Result<Rc<RefCell<Spec<C>>>, ErrKind<C>> {
-    // Make sure there's an argspecs to handle this argument
-    if self.posarg == self.posargs.len() {
-      return Err(ErrKind::MissSpec(
-        "Out of positional argument specs argument".to_string()
-      ));
+    args: &mut Vec<OsString>
+  ) -> Result<SpecRc<C>, ErrKind<C>> {
+    // Make sure there's an argspecs to handle this argument.  Skip specs which
+    // don't get any arguments, because the available arguments are needed by
+    // the specs which follow them.
+    loop {
+      if self.posarg == self.posargs.len() {
+        return Err(ErrKind::MissSpec(
+          "Out of positional argument specs argument".to_string()
+        ));
+      }
+      let spec = self.posargs[self.posarg].borrow();
+      if self.posarg_nargs(&spec) > 0 {
+        break;
+      }
+      drop(spec);
+      self.posarg += 1;
     }
 
     let spec_ref = Rc::clone(&self.posargs[self.posarg]);
+    let index = self.argidx[self.curarg];
 
-    match self.copyout_args(&spec_ref, args) {
-      Ok(_) => {}
-      Err(_err) => {}
-    }
+    self.copyout_args(&spec_ref, args)?;
 
     self.posarg += 1;
 
+    let deprecated = {
+      let spec = spec_ref.borrow();
+      let name = spec.name.clone().unwrap_or_default();
+      spec.get_deprecated().map(|msg| (name, msg.to_string()))
+    };
+    if let Some((name, msg)) = deprecated {
+      self.warn(name, index, msg);
+    }
+
     Ok(spec_ref)
   }
 
 
+  /// The current argument, which was `len` arguments long, has been split
+  /// into several arguments; let them all map to the same original argument.
+  fn split_argidx(&mut self, len: usize) {
+    let idx = self.argidx[self.curarg];
+    let added = self.args.len() - len;
+    self
+      .argidx
+      .splice(self.curarg + 1..self.curarg + 1, vec![idx; added]);
+  }
+
+
+  /// Hand the remaining arguments over to the sub-command parser named by the
+  /// current argument.
+  ///
+  /// The sub-command parser borrows this parser's context while it runs.
+  fn proc_subcmd(&mut self) -> Result<Option<SpecRc<C>>, ErrKind<C>> {
+    let name = self.args[self.curarg].to_string_lossy();
+    let idx = match self.subcmds.iter().position(|x| x.name == name) {
+      Some(idx) => idx,
+      None => {
+        let errstr = format!("Unknown command '{}'", name);
+        let index = self.argidx[self.curarg];
+        return Err(self.attribute(index, ErrKind::UnknownCmd(errstr)));
+      }
+    };
+
+    let sub = &mut self.subcmds[idx].prsr;
+
+    // Let the sub-command parser see the global options.
+    for (c, spec) in &self.sopts {
+      if spec.borrow().is_global() && !sub.sopts.contains_key(c) {
+        sub.sopts.insert(*c, Rc::clone(spec));
+      }
+    }
+    for (o, spec) in &self.lopts {
+      if spec.borrow().is_global() && !sub.lopts.contains_key(o) {
+        sub.lopts.insert(o.clone(), Rc::clone(spec));
+      }
+    }
+    sub.inherited = self
+      .inherited
+      .iter()
+      .chain(&self.specs)
+      .filter(|spec| {
+        spec.borrow().is_global()
+          && sub
+            .sopts
+            .values()
+            .chain(sub.lopts.values())
+            .any(|s| Rc::ptr_eq(s, spec))
+      })
+      .map(Rc::clone)
+      .collect();
+
+    sub.args = self.args[self.curarg + 1..].to_vec();
+    sub.argidx = self.argidx[self.curarg + 1..].to_vec();
+    sub.expanded = self.expanded;
+    sub.posplit = self.posplit;
+    sub.prefix_var.clone_from(&self.prefix_var);
+    sub.curarg = 0;
+
+    std::mem::swap(&mut self.ctx, &mut sub.ctx);
+    std::mem::swap(&mut self.warning_sink, &mut sub.warning_sink);
+    let res = sub.parse();
+    std::mem::swap(&mut self.ctx, &mut sub.ctx);
+    std::mem::swap(&mut self.warning_sink, &mut sub.warning_sink);
+    self.warnings.append(&mut sub.warnings);
+    self.unrecognized.append(&mut sub.unrecognized);
+
+    // Global options are recorded by this parser too.
+    for spec in &sub.inherited {
+      self.matches.merge(&sub.matches, &spec.borrow());
+    }
+
+    self.subcmd = Some(idx);
+    self.curarg = self.args.len();
+
+    res
+  }
+
+
   // If this argspec has arguments, then copy arguments to an argument vector.
   fn copyout_args(
     &mut self,
-    spec_rc: &Rc<RefCell<Spec<C>>>,
-    args: &mut Vec<String>
+    spec_rc: &SpecRc<C>,
+    args: &mut Vec<OsString>
   ) -> Result<(), ErrKind<C>> {
     let spec = spec_rc.borrow();
 
-    // If this is is a "capture the rest of the arguments" spec, then captire
-    // the rest of the arguments.
-    if spec.is_capture_rest() {
-      while self.curarg < self.args.len() {
-        args.push(self.args[self.curarg].clone());
-        self.curarg += 1;
-      }
-
-      // Avoid indexing past the end of the vector later on.
-      self.curarg -= 1;
-
-      return Ok(());
+    // Positional arguments start at the current argument, while an option's
+    // arguments follow the option itself.
+    let first = if spec.is_pos() {
+      self.curarg
+    } else {
+      self.curarg + 1
+    };
+    // Only clear the attached argument if it belongs to this option; an
+    // earlier option in a bundle like "-vcalways" leaves it in place.
+    let attached = self.attached == Some(first);
+    if attached {
+      self.attached = None;
     }
 
     // If this argspec requires arguments then make sure there are enough
@@ -392,24 +1155,87 @@ existing 'capture all' argument"
       return Err(ErrKind::MissArg(err));
     }
 
-    let mut nargs = spec.get_nargs();
-    while nargs != 0 {
-      if spec.is_pos() == false {
-        self.curarg += 1;
-      }
-      args.push(self.args[self.curarg].clone());
-      if spec.is_pos() == true {
-        self.curarg += 1;
+    let nargs = if spec.is_pos() {
+      self.posarg_nargs(&spec)
+    } else {
+      self.optarg_nargs(&spec, attached)
+    };
+
+    check_choices(spec_rc, &self.args[first..first + nargs])?;
+    args.extend_from_slice(&self.args[first..first + nargs]);
+
+    // Leave the current argument index at the last consumed argument.
+    self.curarg = first + nargs - 1;
+
+    Ok(())
+  }
+
+
+  /// Determine how many arguments an option takes, given the arguments which
+  /// follow it.
+  fn optarg_nargs(&self, spec: &Spec<C>, attached: bool) -> usize {
+    let first = self.curarg + 1;
+    match spec.nargs() {
+      Nargs::None => 0,
+      Nargs::Count(n) => n,
+      // Optional arguments are only taken if they're attached to the option.
+      Nargs::Optional => usize::from(attached),
+      Nargs::Remainder => self.args.len() - first,
+      Nargs::ZeroOrMore | Nargs::OneOrMore | Nargs::Range(_, _) => {
+        let max = spec.max_nargs().unwrap_or(usize::MAX);
+        let mut n = spec.min_nargs().max(usize::from(attached));
+        while n < max
+          && first + n < self.args.len()
+          && !self.is_optlike(&self.args[first + n])
+        {
+          n += 1;
+        }
+        n
       }
-      nargs -= 1;
     }
+  }
 
-    // Special case: Positional arguments don't have an option argument
-    if spec.is_pos() {
-      self.curarg -= 1;
+
+  /// Determine how many arguments the current positional argument spec
+  /// takes.
+  ///
+  /// Specs with a variable number of arguments are greedy, but leave enough
+  /// arguments for the minimum argument counts of the positional argument
+  /// specs which follow them.  Only the arguments up to the next option are
+  /// counted; arguments after it are not added to this spec.
+  fn posarg_nargs(&self, spec: &Spec<C>) -> usize {
+    let first = self.curarg;
+    match spec.nargs() {
+      Nargs::None => 0,
+      Nargs::Count(n) => n,
+      Nargs::Remainder => self.args.len() - first,
+      Nargs::Optional
+      | Nargs::ZeroOrMore
+      | Nargs::OneOrMore
+      | Nargs::Range(_, _) => {
+        let min = spec.min_nargs();
+        let max = spec.max_nargs().unwrap_or(usize::MAX);
+        let reserved: usize = self.posargs[self.posarg + 1..]
+          .iter()
+          .map(|x| x.borrow().min_nargs())
+          .sum();
+        let avail = self.args[first..]
+          .iter()
+          .take_while(|x| !self.is_optlike(x))
+          .count();
+        avail.saturating_sub(reserved).min(max).max(min)
+      }
     }
+  }
 
-    Ok(())
+
+  /// Determine whether an argument would be interpreted as an option (or the
+  /// end of options).
+  fn is_optlike(&self, arg: &OsStr) -> bool {
+    if self.posplit {
+      return false;
+    }
+    arg == "--" || prsrutil::maybe_lopt(arg) || prsrutil::maybe_sopt(arg)
   }
 
 
@@ -425,7 +1251,7 @@ existing 'capture all' argument"
   }
 
 
-  fn get_opts(&self) -> Vec<Rc<RefCell<Spec<C>>>> {
+  pub(crate) fn get_opts(&self) -> Vec<SpecRc<C>> {
     self
       .specs
       .iter()
@@ -433,12 +1259,12 @@ existing 'capture all' argument"
         let x = x.borrow();
         x.is_opt()
       })
-      .map(|n| Rc::clone(n))
+      .map(Rc::clone)
       .collect()
   }
 
 
-  fn get_posargs(&self) -> Vec<Rc<RefCell<Spec<C>>>> {
+  pub(crate) fn get_posargs(&self) -> Vec<SpecRc<C>> {
     self
       .specs
       .iter()
@@ -446,12 +1272,40 @@ existing 'capture all' argument"
         let x = x.borrow();
         x.is_pos()
       })
-      .map(|n| Rc::clone(n))
+      .map(Rc::clone)
       .collect()
   }
 
 
+  /// Make sure all required options and positional arguments have been
+  /// processed.
+  ///
+  /// All missing required options are reported at once, by their usage
+  /// strings, in [`ErrKind::MissOpt`].
   pub fn validate(&self) -> Result<(), ErrKind<C>> {
+    let missing: Vec<SpecRc<C>> = self
+      .get_opts()
+      .into_iter()
+      .filter(|spec| {
+        let spec = spec.borrow();
+        spec.is_req() && spec.seen == 0
+      })
+      .collect();
+    if !missing.is_empty() {
+      let opts = missing
+        .iter()
+        .map(|s| s.borrow().get_err_usage_str())
+        .collect();
+      return Err(ErrKind::MissOpt(MissOptErr {
+        specs: missing,
+        opts
+      }));
+    }
+
+    for group in &self.groups {
+      group.validate()?;
+    }
+
     for i in self.posarg..self.posargs.len() {
       let spec = self.posargs[i].borrow();
       if spec.is_req() {
@@ -480,21 +1334,24 @@ existing 'capture all' argument"
   ///
   /// [positional arguments]
   ///
+  /// [commands]
+  ///
   /// [bottom help]
   /// ```
   pub fn usage(&self, out: &mut dyn std::io::Write) {
     self.print_usage(out);
 
     if !self.tophelp.is_empty() {
-      println!("");
+      writeln!(out).expect("Unable to write output.");
     }
     self.print_tophelp(out);
 
     self.print_opts(out);
     self.print_posargs(out);
+    self.print_subcmds(out);
 
     if !self.bottomhelp.is_empty() {
-      println!("");
+      writeln!(out).expect("Unable to write output.");
     }
 
     self.print_bottomhelp(out);
@@ -514,31 +1371,47 @@ existing 'capture all' argument"
 
     sv.push(String::from("Usage:"));
     sv.push(self.argv0.clone());
+    sv.append(&mut self.get_usage_words());
 
-    /*
-    self.specs.iter().map(|n| {
-      let n = n.borrow();
-      sv.push(n.get_usage_str());
-    });
-    */
-    for n in &self.specs {
-      let n = n.borrow();
+    pp.set_indent(7).set_hang(-7);
+    pp.print_words(out, &sv);
+  }
+
+  /// Get the words of the usage line which follow the command name.
+  pub(crate) fn get_usage_words(&self) -> Vec<String> {
+    let mut sv = Vec::new();
+
+    // Options in groups which are shown as a unit are replaced by their
+    // group, at the position of the group's first option.
+    let mut shown: Vec<&OptGroup<C>> = Vec::new();
+    for spec in self.inherited.iter().chain(&self.specs) {
+      let group = self.groups.iter().find(|g| {
+        g.get_usage_str().is_some()
+          && g.specs.iter().any(|s| Rc::ptr_eq(s, spec))
+      });
+      if let Some(group) = group {
+        if !shown.iter().any(|g| std::ptr::eq(*g, group)) {
+          shown.push(group);
+          if let Some(s) = group.get_usage_str() {
+            sv.push(s);
+          }
+        }
+        continue;
+      }
+
+      let n = spec.borrow();
       if n.is_hidden() {
         continue;
       }
       sv.push(n.get_usage_str());
     }
 
-    /*
-    let sv: Vec<String> = self.get_opts().iter().map(|n| {
-      let n = n.borrow();
-      //sv.push(n.get_usage_str());
-      return n.get_usage_str();
-    }).collect();
-    */
+    if !self.subcmds.is_empty() {
+      sv.push(String::from("<COMMAND>"));
+      sv.push(String::from("[ARGS ...]"));
+    }
 
-    pp.set_indent(7).set_hang(-7);
-    pp.print_words(out, &sv);
+    sv
   }
 
 
@@ -570,12 +1443,15 @@ existing 'capture all' argument"
   pub fn print_opts(&self, out: &mut dyn std::io::Write) {
     let mut pp = pprint::PPrint::new();
 
-    let opts = self.get_opts();
-    if opts.len() == 0 {
+    let mut opts = self.inherited.clone();
+    opts.append(&mut self.get_opts());
+    if opts.is_empty() {
       return;
     }
 
-    out.write(b"\noptions:\n").expect("Unable to write output.");
+    out
+      .write_all(b"\noptions:\n")
+      .expect("Unable to write output.");
 
     for spec in &opts {
       let spec = spec.borrow();
@@ -585,7 +1461,7 @@ existing 'capture all' argument"
       pp.set_indent(2);
       pp.print_p(out, &spec.get_opts_usage_str());
       pp.set_indent(4);
-      pp.print_plist(out, spec.get_help_text());
+      pp.print_plist(out, spec.get_full_help_text());
     }
   }
 
@@ -594,12 +1470,12 @@ existing 'capture all' argument"
     let mut pp = pprint::PPrint::new();
 
     let specs = self.get_posargs();
-    if specs.len() == 0 {
+    if specs.is_empty() {
       return;
     }
 
     out
-      .write(b"\narguments:\n")
+      .write_all(b"\narguments:\n")
       .expect("Unable to write output.");
 
     for spec in &specs {
@@ -607,15 +1483,50 @@ existing 'capture all' argument"
       pp.set_indent(2);
       pp.print_p(out, &spec.get_help_title_str());
       pp.set_indent(4);
-      pp.print_plist(out, spec.get_help_text());
+      pp.print_plist(out, spec.get_full_help_text());
+    }
+  }
+
+
+  /// Print the help section for sub-commands.
+  pub fn print_subcmds(&self, out: &mut dyn std::io::Write) {
+    let mut pp = pprint::PPrint::new();
+
+    if self.subcmds.is_empty() {
+      return;
+    }
+
+    out
+      .write_all(b"\ncommands:\n")
+      .expect("Unable to write output.");
+
+    for sub in &self.subcmds {
+      pp.set_indent(2);
+      pp.print_p(out, &sub.name);
+      pp.set_indent(4);
+      pp.print_plist(out, &sub.help);
     }
   }
 
 
+  /// Return boolean indicating whether iterating over the parser stopped
+  /// because of an error.
   pub fn did_fail(&self) -> bool {
     self.err.is_some()
   }
 
+  /// Get the error which stopped the parser when it was used as an
+  /// `Iterator`.
+  pub fn get_err(&self) -> Option<&ErrKind<C>> {
+    self.err.as_ref()
+  }
+
+  /// Take the error which stopped the parser when it was used as an
+  /// `Iterator`.
+  pub fn take_err(&mut self) -> Option<ErrKind<C>> {
+    self.err.take()
+  }
+
   pub fn get_ctx(&self) -> &C {
     &self.ctx
   }
@@ -625,17 +1536,128 @@ existing 'capture all' argument"
   }
 }
 
+
+/// Get the arguments for a spec from its environment variable, if it has one
+/// and it's set to a non-empty value.
+fn get_env_args<C>(
+  spec_rc: &SpecRc<C>
+) -> Result<Option<Vec<OsString>>, ErrKind<C>> {
+  let spec = spec_rc.borrow();
+  let var = match spec.env {
+    Some(ref var) => var,
+    None => return Ok(None)
+  };
+  let val = match env::var_os(var) {
+    Some(val) if !val.is_empty() => val,
+    _ => return Ok(None)
+  };
+
+  if !spec.takes_args() {
+    return match prsrutil::parse_bool(&val) {
+      Some(true) => Ok(Some(Vec::new())),
+      Some(false) => Ok(None),
+      None => Err(ErrKind::BadArg(ArgErr {
+        spec: Rc::clone(spec_rc),
+        args: vec![val],
+        msg: format!("Expected a boolean in environment variable '{}'", var)
+      }))
+    };
+  }
+  if spec.max_nargs() == Some(1) {
+    return Ok(Some(vec![val]));
+  }
+
+  let args = prsrutil::split_os_whitespace(&val);
+  let count_ok = args.len() >= spec.min_nargs()
+    && !matches!(spec.max_nargs(), Some(max) if args.len() > max);
+  if !count_ok {
+    return Err(ErrKind::BadArg(ArgErr {
+      spec: Rc::clone(spec_rc),
+      args: vec![val],
+      msg: format!(
+        "Wrong number of arguments in environment variable '{}'",
+        var
+      )
+    }));
+  }
+  Ok(Some(args))
+}
+
+
+/// Make sure all arguments are among the spec's choices, if it has any.
+fn check_choices<C>(
+  spec_rc: &SpecRc<C>,
+  args: &[OsString]
+) -> Result<(), ErrKind<C>> {
+  let spec = spec_rc.borrow();
+  let choices = match spec.choices {
+    Some(ref choices) => choices,
+    None => return Ok(())
+  };
+
+  for arg in args {
+    if !choices.iter().any(|c| arg == c.as_str()) {
+      return Err(ErrKind::BadChoice(ChoiceErr {
+        spec: Rc::clone(spec_rc),
+        arg: arg.clone(),
+        choices: choices.clone()
+      }));
+    }
+  }
+  Ok(())
+}
+
+/// Convert a spec's arguments to UTF-8 strings.
+fn to_strings<C>(
+  spec_rc: &SpecRc<C>,
+  args: Vec<OsString>
+) -> Result<Vec<String>, ErrKind<C>> {
+  let mut sargs = Vec::with_capacity(args.len());
+  for arg in args {
+    match arg.into_string() {
+      Ok(s) => sargs.push(s),
+      Err(arg) => {
+        let err = SpecErr {
+          spec: Rc::clone(spec_rc),
+          msg: format!(
+            "Argument '{}' is not valid UTF-8.",
+            arg.to_string_lossy()
+          )
+        };
+        return Err(ErrKind::InvalidUtf8(err));
+      }
+    }
+  }
+  Ok(sargs)
+}
+
+
+impl<C: Default> Parser<C> {
+  /// Create a parser which is meant to be registered as a sub-command using
+  /// [`Parser::add_subcmd()`](Parser::add_subcmd).
+  ///
+  /// The parser is given a default context as a placeholder; while parsing,
+  /// the sub-command parser operates on its parent's context.
+  pub fn new_subcmd() -> Self {
+    Parser::from_args("", Vec::<OsString>::new(), C::default())
+  }
+}
+
 impl<C> Iterator for Parser<C> {
-  type Item = Rc<RefCell<Spec<C>>>;
+  type Item = SpecRc<C>;
 
+  /// Errors end the iteration.  Use [`Parser::get_err()`](Parser::get_err)
+  /// or [`Parser::take_err()`](Parser::take_err) to find out what went
+  /// wrong.
   fn next(&mut self) -> Option<Self::Item> {
+    if self.err.is_some() {
+      return None;
+    }
     match self.next() {
-      Ok(res) => {
-        return res;
-      }
+      Ok(res) => res,
       Err(err) => {
         self.err = Some(err);
-        return None;
+        None
       }
     }
   }
@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
 use std::rc::Rc;
 //use std::env;
 
@@ -12,43 +13,71 @@ use crate::spec::{Builder, Nargs};
 
 #[cfg(test)]
 macro_rules! vec_of_strings {
-  ($($x:expr),*) => (vec![$($x.to_string()),*]);
+  ($($x:expr),*) => (vec![$(OsString::from($x)),*]);
+}
+
+/// Get a sub-slice of an `OsStr`, using byte offsets into its encoded
+/// representation.
+///
+/// The caller must make sure that `start` and `end` are adjacent to an ASCII
+/// character or a complete UTF-8 character within `s`.
+pub(crate) fn os_slice(s: &OsStr, start: usize, end: usize) -> &OsStr {
+  let bytes = s.as_encoded_bytes();
+  // SAFETY: Splitting next to valid UTF-8 is explicitly allowed by the
+  // `OsStr` encoding contract, and the caller guarantees this is the case.
+  unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[start..end]) }
+}
+
+/// Decode the character at the start of a byte buffer, returning it along
+/// with its encoded length.  Returns `None` if the buffer does not begin with
+/// valid UTF-8.
+pub(crate) fn decode_char(bytes: &[u8]) -> Option<(char, usize)> {
+  for len in 1..=bytes.len().min(4) {
+    if let Ok(s) = std::str::from_utf8(&bytes[..len]) {
+      return s.chars().next().map(|c| (c, len));
+    }
+  }
+  None
+}
+
+#[test]
+fn test_decode_char() {
+  assert_eq!(decode_char(b"abc"), Some(('a', 1)));
+  assert_eq!(decode_char("\u{e5}x".as_bytes()), Some(('\u{e5}', 2)));
+  assert_eq!(decode_char(b"\xffa"), None);
+  assert_eq!(decode_char(b""), None);
 }
 
 /// Determine whether an arguments vector element looks like it could be a
 /// long option.
-pub(crate) fn maybe_lopt(arg: &str) -> bool {
-  if arg.len() > 2 && arg.starts_with("--") == true {
-    return true;
-  }
-  false
+pub(crate) fn maybe_lopt(arg: &OsStr) -> bool {
+  let arg = arg.as_encoded_bytes();
+  arg.len() > 2 && arg.starts_with(b"--")
 }
 
 #[test]
 fn test_maybe_lopt() {
-  assert_eq!(maybe_lopt("foo"), false);
-  assert_eq!(maybe_lopt("-f"), false);
-  assert_eq!(maybe_lopt("--"), false);
-  assert_eq!(maybe_lopt("--foo"), true);
-  assert_eq!(maybe_lopt("--f"), true);
-  assert_eq!(maybe_lopt(" --foo"), false);
+  assert!(!maybe_lopt(OsStr::new("foo")));
+  assert!(!maybe_lopt(OsStr::new("-f")));
+  assert!(!maybe_lopt(OsStr::new("--")));
+  assert!(maybe_lopt(OsStr::new("--foo")));
+  assert!(maybe_lopt(OsStr::new("--f")));
+  assert!(!maybe_lopt(OsStr::new(" --foo")));
 }
 
 
 /// Determine whether an arguments vector element looks like it could be a
 /// short option.
-pub(crate) fn maybe_sopt(arg: &str) -> bool {
-  if arg.len() > 1 && arg.starts_with("-") == true {
-    return true;
-  }
-  false
+pub(crate) fn maybe_sopt(arg: &OsStr) -> bool {
+  let arg = arg.as_encoded_bytes();
+  arg.len() > 1 && arg.starts_with(b"-")
 }
 
 #[test]
 fn test_maybe_sopt() {
-  assert_eq!(maybe_sopt("foo"), false);
-  assert_eq!(maybe_sopt("-"), false);
-  assert_eq!(maybe_sopt("-a"), true);
+  assert!(!maybe_sopt(OsStr::new("foo")));
+  assert!(!maybe_sopt(OsStr::new("-")));
+  assert!(maybe_sopt(OsStr::new("-a")));
 }
 
 
@@ -60,56 +89,92 @@ fn is_end_of_opts(arg: &str) -> bool {
 */
 
 
+/// Split bundled short options, and a trailing argument attached to the last
+/// option, into separate arguments vector elements.
+///
+/// Returns the index of the attached argument, if there is one.  If an unknown
+/// option, or a byte sequence which isn't a valid character, is encountered
+/// before an option taking arguments, the arguments are left as they are and
+/// the offending option is returned as an error.
+///
+/// With `passthrough` set, an unknown option which follows known ones is
+/// instead split off along with the rest of the argument, like `-vxf` into
+/// `-v` and `-xf`.  Only an unknown first option is returned as an error.
 pub(crate) fn split_sopts_arg<C>(
-  args: &mut Vec<String>,
+  args: &mut Vec<OsString>,
   argidx: usize,
-  sopts: &HashMap<char, Rc<RefCell<Spec<C>>>>
-) {
-  let curarg = &args[argidx][1..].to_string().clone();
-  let chars: Vec<char> = curarg.chars().collect();
-  let mut optarg: Option<String> = None;
-
+  sopts: &HashMap<char, Rc<RefCell<Spec<C>>>>,
+  passthrough: bool
+) -> Result<Option<usize>, OsString> {
+  let curarg = args[argidx].clone();
+  let bytes = &curarg.as_encoded_bytes()[1..];
+  let mut chars: Vec<char> = Vec::new();
+  let mut optarg: Option<OsString> = None;
+  let mut unknown: Option<OsString> = None;
+
+  // Byte offset into the argument, excluding the leading '-'.
   let mut idx: usize = 0;
-  while idx < chars.len() {
-    let opt_spec = sopts.get(&chars[idx]);
-    if let Some(ref spec_rc) = opt_spec {
-      let spec = spec_rc.borrow();
-      if spec.req_args() {
-        // spec has arguments -- break out of split loop
-        idx += 1;
+  while idx < bytes.len() {
+    let decoded = decode_char(&bytes[idx..]);
+    let (ch, len, spec_rc) = match decoded {
+      Some((ch, len)) if sopts.contains_key(&ch) => (ch, len, &sopts[&ch]),
+      _ if passthrough && idx > 0 => {
+        // Split off the known options, and leave the unknown one along with
+        // the rest of the argument, which may be its argument.
+        let mut rest = OsString::from("-");
+        rest.push(os_slice(&curarg, idx + 1, bytes.len() + 1));
+        unknown = Some(rest);
         break;
       }
-    } else {
-      panic!("Unknown option");
+      Some((ch, _)) => return Err(OsString::from(format!("-{}", ch))),
+      None => {
+        let ch = String::from_utf8_lossy(&bytes[idx..idx + 1]);
+        return Err(OsString::from(format!("-{}", ch)));
+      }
+    };
+    chars.push(ch);
+    idx += len;
+
+    let spec = spec_rc.borrow();
+    if spec.takes_args() {
+      // spec has arguments -- break out of split loop
+      break;
     }
-    idx += 1;
   }
 
   // If the loop broke out before reaching the end then interpret that as there
   // being an trailing argument.
-  if idx != curarg.len() {
-    optarg = Some(curarg[idx..].to_string());
+  if idx != bytes.len() && unknown.is_none() {
+    let end = bytes.len() + 1;
+    optarg = Some(os_slice(&curarg, idx + 1, end).to_os_string());
   }
 
-  if idx > 1 || optarg.is_some() {
+  if chars.len() > 1 || optarg.is_some() || unknown.is_some() {
     // Remove the transformed argument
     args.remove(argidx);
 
     let mut i: usize = 0;
-    while i < idx {
-      let opt = "-".to_string() + &chars[i].to_string();
-      args.insert(argidx + i, opt);
+    while i < chars.len() {
+      let opt = format!("-{}", chars[i]);
+      args.insert(argidx + i, OsString::from(opt));
       i += 1;
     }
 
+    if let Some(unknown) = unknown {
+      args.insert(argidx + i, unknown);
+    }
+
     if let Some(optarg) = optarg {
       args.insert(argidx + i, optarg);
+      return Ok(Some(argidx + i));
     }
   }
+  Ok(None)
 }
 
 
 #[cfg(test)]
+#[allow(dead_code)]
 mod tests {
   #[derive(Default)]
   pub(super) struct TestCtx {
@@ -154,7 +219,7 @@ fn test_split_sopt1() {
   sopts.insert('h', Rc::new(RefCell::new(spec)));
 
   let mut args = vec_of_strings!["-h"];
-  split_sopts_arg(&mut args, 0, &sopts);
+  split_sopts_arg(&mut args, 0, &sopts, false).unwrap();
   assert_eq!(args.len(), 1);
   assert_eq!(args[0], "-h");
 }
@@ -179,13 +244,13 @@ fn test_split_sopt2() {
   sopts.insert('v', Rc::new(RefCell::new(spec_v)));
 
   let mut args = vec_of_strings!["-v", "-f", "bar"];
-  split_sopts_arg(&mut args, 0, &sopts);
+  split_sopts_arg(&mut args, 0, &sopts, false).unwrap();
   assert_eq!(args.len(), 3);
   assert_eq!(args[0], "-v");
   assert_eq!(args[1], "-f");
   assert_eq!(args[2], "bar");
 
-  split_sopts_arg(&mut args, 1, &sopts);
+  split_sopts_arg(&mut args, 1, &sopts, false).unwrap();
   assert_eq!(args.len(), 3);
   assert_eq!(args[0], "-v");
   assert_eq!(args[1], "-f");
@@ -209,7 +274,7 @@ fn test_split_sopt3() {
   let mut args = vec_of_strings!["-fbar"];
   //println!("{:?}", args);
 
-  split_sopts_arg(&mut args, 0, &sopts);
+  split_sopts_arg(&mut args, 0, &sopts, false).unwrap();
   assert_eq!(args.len(), 2);
   assert_eq!(args[0], "-f");
   assert_eq!(args[1], "bar");
@@ -236,7 +301,7 @@ fn test_split_sopt4() {
   sopts.insert('v', Rc::new(RefCell::new(spec_v)));
 
   let mut args = vec_of_strings!["-vfbar"];
-  split_sopts_arg(&mut args, 0, &sopts);
+  assert_eq!(split_sopts_arg(&mut args, 0, &sopts, false), Ok(Some(2)));
   assert_eq!(args.len(), 3);
   assert_eq!(args[0], "-v");
   assert_eq!(args[1], "-f");
@@ -247,18 +312,18 @@ fn test_split_sopt4() {
 /// Ensure that there are sufficient arguments remaining for argspec to
 /// process.
 pub(crate) fn check_req_arg_count<C>(
-  args: &Vec<String>,
+  args: &[OsString],
   idx: usize,
   spec: &Spec<C>,
   offset: bool
 ) -> bool {
-  if spec.get_nargs() != 0 {
-    let nremain = if offset == true {
+  if spec.min_nargs() != 0 {
+    let nremain = if offset {
       args.len() - idx - 1
     } else {
       args.len() - idx
     };
-    if nremain < spec.get_nargs() {
+    if nremain < spec.min_nargs() {
       return false;
     }
   }
@@ -267,28 +332,28 @@ pub(crate) fn check_req_arg_count<C>(
 
 
 // --file=foo  -->  --file foo
-pub(crate) fn split_lopt(argv: &mut Vec<String>, i: usize) {
-  match argv[i].find('=') {
-    None => (),
-    Some(idx) => {
-      if idx == 0 || idx == argv[i].len() - 1 {
-        ()
-      }
-
-      let l = argv[i][..idx].to_string();
-      let r = argv[i][idx + 1..].to_string();
-
-      argv[i] = l;
-      argv.insert(i + 1, r);
-    }
+//
+// Returns `true` if the argument was split.
+pub(crate) fn split_lopt(argv: &mut Vec<OsString>, i: usize) -> bool {
+  let arg = argv[i].clone();
+  let bytes = arg.as_encoded_bytes();
+  if let Some(idx) = bytes.iter().position(|b| *b == b'=') {
+    // Splitting around an ASCII '=' always yields valid `OsStr`s.
+    let l = os_slice(&arg, 0, idx).to_os_string();
+    let r = os_slice(&arg, idx + 1, bytes.len()).to_os_string();
+
+    argv[i] = l;
+    argv.insert(i + 1, r);
+    return true;
   }
+  false
 }
 
 #[test]
 fn test_split_lopt_eq() {
   let mut argv = vec_of_strings!["--foo=bar"];
 
-  split_lopt(&mut argv, 0);
+  assert!(split_lopt(&mut argv, 0));
   assert_eq!(argv.len(), 2);
   assert_eq!(argv[0], "--foo");
   assert_eq!(argv[1], "bar");
@@ -298,7 +363,7 @@ fn test_split_lopt_eq() {
 fn test_split_lopt_neq() {
   let mut argv = vec_of_strings!["--foo", "bar"];
 
-  split_lopt(&mut argv, 0);
+  assert!(!split_lopt(&mut argv, 0));
   assert_eq!(argv.len(), 2);
   assert_eq!(argv[0], "--foo");
   assert_eq!(argv[1], "bar");
@@ -338,4 +403,351 @@ fn test_split_lopt_eq_right() {
   assert_eq!(argv[2], "bar");
 }
 
+#[test]
+fn test_split_sopt_optional() {
+  let mut sopts: HashMap<char, Rc<RefCell<Spec<tests::TestCtx>>>> =
+    HashMap::new();
+
+  let spec_c = Builder::new()
+    .sopt('c')
+    .nargs(Nargs::Optional, &["WHEN"])
+    .build(tests::file_proc);
+  let spec_v = Builder::new().sopt('v').build(tests::verbose_proc);
+
+  sopts.insert('c', Rc::new(RefCell::new(spec_c)));
+  sopts.insert('v', Rc::new(RefCell::new(spec_v)));
+
+  let mut args = vec_of_strings!["-vc", "never"];
+  assert_eq!(split_sopts_arg(&mut args, 0, &sopts, false), Ok(None));
+  assert_eq!(args, vec_of_strings!["-v", "-c", "never"]);
+
+  let mut args = vec_of_strings!["-vcnever"];
+  assert_eq!(split_sopts_arg(&mut args, 0, &sopts, false), Ok(Some(2)));
+  assert_eq!(args, vec_of_strings!["-v", "-c", "never"]);
+}
+
+
+#[test]
+fn test_split_sopt_unknown() {
+  let mut sopts: HashMap<char, Rc<RefCell<Spec<tests::TestCtx>>>> =
+    HashMap::new();
+  let spec_v = Builder::new().sopt('v').build(tests::file_proc);
+  sopts.insert('v', Rc::new(RefCell::new(spec_v)));
+
+  let mut args = vec_of_strings!["-vx"];
+  assert_eq!(
+    split_sopts_arg(&mut args, 0, &sopts, false),
+    Err(OsString::from("-x"))
+  );
+  assert_eq!(args, vec_of_strings!["-vx"]);
+
+  let mut args = vec_of_strings!["-x"];
+  assert_eq!(
+    split_sopts_arg(&mut args, 0, &sopts, false),
+    Err(OsString::from("-x"))
+  );
+
+  // Unknown options after known ones are split off when passing them
+  // through.
+  let mut args = vec_of_strings!["-vxvy", "a"];
+  assert_eq!(split_sopts_arg(&mut args, 0, &sopts, true), Ok(None));
+  assert_eq!(args, vec_of_strings!["-v", "-xvy", "a"]);
+
+  let mut args = vec_of_strings!["-xv"];
+  assert_eq!(
+    split_sopts_arg(&mut args, 0, &sopts, true),
+    Err(OsString::from("-x"))
+  );
+  assert_eq!(args, vec_of_strings!["-xv"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_split_non_utf8() {
+  use std::os::unix::ffi::OsStrExt;
+
+  let mut sopts: HashMap<char, Rc<RefCell<Spec<tests::TestCtx>>>> =
+    HashMap::new();
+  let spec_f = Builder::new()
+    .sopt('f')
+    .nargs(Nargs::Count(1), &["FILE"])
+    .build(tests::file_proc);
+  sopts.insert('f', Rc::new(RefCell::new(spec_f)));
+
+  let mut args = vec![OsStr::from_bytes(b"-f\xffbar").to_os_string()];
+  split_sopts_arg(&mut args, 0, &sopts, false).unwrap();
+  assert_eq!(args.len(), 2);
+  assert_eq!(args[0], "-f");
+  assert_eq!(args[1].as_bytes(), b"\xffbar");
+
+  let mut argv = vec![OsStr::from_bytes(b"--file=\xfe.txt").to_os_string()];
+  split_lopt(&mut argv, 0);
+  assert_eq!(argv.len(), 2);
+  assert_eq!(argv[0], "--file");
+  assert_eq!(argv[1].as_bytes(), b"\xfe.txt");
+}
+
+
+/// Compute the edit distance between two strings, counting insertions,
+/// deletions, substitutions and transpositions of adjacent characters.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+  let a: Vec<char> = a.chars().collect();
+  let b: Vec<char> = b.chars().collect();
+
+  // d[i][j] is the distance between the first i characters of a and the
+  // first j characters of b.
+  let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+  for (i, row) in d.iter_mut().enumerate() {
+    row[0] = i;
+  }
+  for (j, cell) in d[0].iter_mut().enumerate() {
+    *cell = j;
+  }
+
+  for i in 1..=a.len() {
+    for j in 1..=b.len() {
+      let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+      d[i][j] = (d[i - 1][j] + 1)
+        .min(d[i][j - 1] + 1)
+        .min(d[i - 1][j - 1] + cost);
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+      }
+    }
+  }
+
+  d[a.len()][b.len()]
+}
+
+#[test]
+fn test_edit_distance() {
+  assert_eq!(edit_distance("verbose", "verbose"), 0);
+  assert_eq!(edit_distance("verbse", "verbose"), 1);
+  assert_eq!(edit_distance("vrebose", "verbose"), 1);
+  assert_eq!(edit_distance("colour", "color"), 1);
+  assert_eq!(edit_distance("", "help"), 4);
+  assert_eq!(edit_distance("help", "file"), 3);
+}
+
+
+/// Find the candidate closest to `word`, if any candidate is close enough to
+/// be a likely typo.  Ties are broken by picking the lexicographically
+/// smallest candidate.
+pub(crate) fn closest<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+  I: IntoIterator<Item = &'a str>
+{
+  // Allow one edit for short words, and up to a third of the word for longer
+  // ones.
+  let limit = std::cmp::max(1, word.chars().count() / 3);
+
+  candidates
+    .into_iter()
+    .map(|c| (edit_distance(word, c), c))
+    .filter(|(dist, _)| *dist <= limit)
+    .min()
+    .map(|(_, c)| c)
+}
+
+#[test]
+fn test_closest() {
+  let opts = ["verbose", "version", "help"];
+  assert_eq!(closest("verbse", opts.iter().copied()), Some("verbose"));
+  assert_eq!(closest("verison", opts.iter().copied()), Some("version"));
+  assert_eq!(closest("hlep", opts.iter().copied()), Some("help"));
+  assert_eq!(closest("frobnicate", opts.iter().copied()), None);
+  assert_eq!(closest("x", opts.iter().copied()), None);
+}
+
+/// Collects the bytes of a word as runs of the string being split, so that
+/// bytes which aren't valid UTF-8 are kept as they are.
+struct WordBuf<'a> {
+  s: &'a OsStr,
+  word: OsString,
+  run: std::ops::Range<usize>
+}
+
+impl WordBuf<'_> {
+  /// Add the byte at index `i` to the word.
+  fn lit(&mut self, i: usize) {
+    if self.run.end != i {
+      self.flush();
+      self.run = i..i;
+    }
+    self.run.end = i + 1;
+  }
+
+  fn flush(&mut self) {
+    // Runs only end next to the ASCII characters which were left out.
+    self.word.push(os_slice(self.s, self.run.start, self.run.end));
+    self.run = 0..0;
+  }
+
+  fn take(&mut self) -> OsString {
+    self.flush();
+    std::mem::take(&mut self.word)
+  }
+}
+
+/// Split a string into words the way a POSIX shell would, honoring single
+/// quotes, double quotes and backslash escapes.  Nothing is expanded.  Words
+/// are separated by ASCII whitespace; all other bytes, including ones which
+/// aren't valid UTF-8, are kept as they are.
+pub(crate) fn split_words(s: &OsStr) -> Result<Vec<OsString>, String> {
+  let mut words = Vec::new();
+  let mut buf = WordBuf {
+    s,
+    word: OsString::new(),
+    run: 0..0
+  };
+  // Quotes start a word even if they're empty, as in `''`.
+  let mut inword = false;
+  let mut bytes = s.as_encoded_bytes().iter().copied().enumerate();
+
+  while let Some((i, b)) = bytes.next() {
+    match b {
+      b'\'' => {
+        inword = true;
+        loop {
+          match bytes.next() {
+            Some((_, b'\'')) => break,
+            Some((j, _)) => buf.lit(j),
+            None => return Err("Unterminated single quote".to_string())
+          }
+        }
+      }
+      b'"' => {
+        inword = true;
+        loop {
+          match bytes.next() {
+            Some((_, b'"')) => break,
+            // Within double quotes, backslash only escapes characters which
+            // would otherwise be special.
+            Some((j, b'\\')) => match bytes.next() {
+              Some((k, b'"' | b'\\' | b'$' | b'`')) => buf.lit(k),
+              Some((_, b'\n')) => {}
+              Some((k, _)) => {
+                buf.lit(j);
+                buf.lit(k);
+              }
+              None => return Err("Unterminated double quote".to_string())
+            },
+            Some((j, _)) => buf.lit(j),
+            None => return Err("Unterminated double quote".to_string())
+          }
+        }
+      }
+      b'\\' => match bytes.next() {
+        // Line continuation.
+        Some((_, b'\n')) => {}
+        Some((j, _)) => {
+          inword = true;
+          buf.lit(j);
+        }
+        None => return Err("Trailing backslash".to_string())
+      },
+      b if b.is_ascii_whitespace() => {
+        if inword {
+          words.push(buf.take());
+          inword = false;
+        }
+      }
+      _ => {
+        inword = true;
+        buf.lit(i);
+      }
+    }
+  }
+  if inword {
+    words.push(buf.take());
+  }
+
+  Ok(words)
+}
+
+#[test]
+fn test_split_words() {
+  let split = |s: &str| split_words(OsStr::new(s));
+  assert!(split("").unwrap().is_empty());
+  assert_eq!(split("  -v\t--foo \n").unwrap(), ["-v", "--foo"]);
+  assert_eq!(split("'a b' \"c d\"").unwrap(), ["a b", "c d"]);
+  assert_eq!(split("a'b'\"c\"d").unwrap(), ["abcd"]);
+  assert_eq!(split("'' \"\"").unwrap(), ["", ""]);
+  assert_eq!(split("a\\ b \\'").unwrap(), ["a b", "'"]);
+  assert_eq!(split("\"\\\" \\n\"").unwrap(), ["\" \\n"]);
+  assert_eq!(split("'\\'").unwrap(), ["\\"]);
+  assert_eq!(split("a\\\nb").unwrap(), ["ab"]);
+  let words = split("\\\u{e9}t\u{e9} \"\\\u{e9}\"").unwrap();
+  assert_eq!(words, ["\u{e9}t\u{e9}", "\\\u{e9}"]);
+  assert!(split("'a").is_err());
+  assert!(split("\"a").is_err());
+  assert!(split("a\\").is_err());
+
+  #[cfg(unix)]
+  {
+    use std::os::unix::ffi::OsStrExt;
+    let words = split_words(OsStr::from_bytes(b"'a\xff b' \\\xfe")).unwrap();
+    assert_eq!(words[0].as_bytes(), b"a\xff b");
+    assert_eq!(words[1].as_bytes(), b"\xfe");
+  }
+}
+
+
+/// Split an `OsStr` on ASCII whitespace, leaving everything else, including
+/// bytes which aren't valid UTF-8, as it is.
+pub(crate) fn split_os_whitespace(s: &OsStr) -> Vec<OsString> {
+  let bytes = s.as_encoded_bytes();
+  let mut words = Vec::new();
+  let mut start: Option<usize> = None;
+  for (i, b) in bytes.iter().enumerate() {
+    match (b.is_ascii_whitespace(), start) {
+      (true, Some(st)) => {
+        words.push(os_slice(s, st, i).to_os_string());
+        start = None;
+      }
+      (false, None) => start = Some(i),
+      _ => {}
+    }
+  }
+  if let Some(st) = start {
+    words.push(os_slice(s, st, bytes.len()).to_os_string());
+  }
+  words
+}
+
+#[test]
+fn test_split_os_whitespace() {
+  assert!(split_os_whitespace(OsStr::new(" \t")).is_empty());
+  assert_eq!(split_os_whitespace(OsStr::new(" 3\t4 \n")), ["3", "4"]);
+
+  #[cfg(unix)]
+  {
+    use std::os::unix::ffi::OsStrExt;
+    let words = split_os_whitespace(OsStr::from_bytes(b"caf\xe9 x"));
+    assert_eq!(words[0].as_bytes(), b"caf\xe9");
+    assert_eq!(words[1], "x");
+  }
+}
+
+
+/// Interpret an environment variable value as a boolean.  Accepts `1`,
+/// `true`, `yes` and `on`, and `0`, `false`, `no` and `off`, ignoring case.
+pub(crate) fn parse_bool(s: &OsStr) -> Option<bool> {
+  let s = s.to_str()?.to_ascii_lowercase();
+  match s.as_str() {
+    "1" | "true" | "yes" | "on" => Some(true),
+    "0" | "false" | "no" | "off" => Some(false),
+    _ => None
+  }
+}
+
+#[test]
+fn test_parse_bool() {
+  assert_eq!(parse_bool(OsStr::new("1")), Some(true));
+  assert_eq!(parse_bool(OsStr::new("Yes")), Some(true));
+  assert_eq!(parse_bool(OsStr::new("0")), Some(false));
+  assert_eq!(parse_bool(OsStr::new("OFF")), Some(false));
+  assert_eq!(parse_bool(OsStr::new("2")), None);
+}
+
 /* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
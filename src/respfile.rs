@@ -0,0 +1,123 @@
+//! Response file (`@file`) expansion.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::err::RespFileErr;
+use crate::prsrutil;
+
+
+/// Expands response files into the arguments they contain.
+struct Expander {
+  args: Vec<OsString>,
+  argidx: Vec<usize>,
+
+  /// Set once `--` has been seen; later arguments are left alone.
+  posplit: bool,
+
+  /// Canonical paths of the response files currently being expanded, used
+  /// to detect cycles.
+  stack: Vec<PathBuf>
+}
+
+impl Expander {
+  /// Expand an argument.  `dir` is the directory of the response file the
+  /// argument came from, which relative `@path` arguments are resolved
+  /// against.
+  fn arg(
+    &mut self,
+    arg: OsString,
+    idx: usize,
+    dir: Option<&Path>
+  ) -> Result<(), RespFileErr> {
+    let len = arg.len();
+    let bytes = arg.as_encoded_bytes();
+
+    if self.posplit || len < 2 || bytes[0] != b'@' {
+      self.posplit |= arg == "--";
+      self.args.push(arg);
+      self.argidx.push(idx);
+    } else if bytes[1] == b'@' {
+      // "@@foo" is the literal argument "@foo".
+      let arg = prsrutil::os_slice(&arg, 1, len).to_os_string();
+      self.args.push(arg);
+      self.argidx.push(idx);
+    } else {
+      let path = Path::new(prsrutil::os_slice(&arg, 1, len));
+      let path = match dir {
+        Some(dir) => dir.join(path),
+        None => path.to_path_buf()
+      };
+      self.file(path, idx)?;
+    }
+
+    Ok(())
+  }
+
+  fn file(&mut self, path: PathBuf, idx: usize) -> Result<(), RespFileErr> {
+    let mkerr = |msg: String| RespFileErr {
+      path: path.clone(),
+      msg
+    };
+
+    let canon = fs::canonicalize(&path).map_err(|e| mkerr(e.to_string()))?;
+    if self.stack.contains(&canon) {
+      return Err(mkerr("Response file includes itself".to_string()));
+    }
+
+    let bytes = fs::read(&path).map_err(|e| mkerr(e.to_string()))?;
+    let text = to_os_string(bytes).map_err(mkerr)?;
+    let words = prsrutil::split_words(&text).map_err(mkerr)?;
+
+    self.stack.push(canon);
+    for word in words {
+      self.arg(word, idx, path.parent())?;
+    }
+    self.stack.pop();
+
+    Ok(())
+  }
+}
+
+
+/// Convert the contents of a response file to an `OsString`.  Except on
+/// Unix, where any bytes can be passed as arguments, the contents must be
+/// valid UTF-8.
+#[cfg(unix)]
+fn to_os_string(bytes: Vec<u8>) -> Result<OsString, String> {
+  use std::os::unix::ffi::OsStringExt;
+  Ok(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn to_os_string(bytes: Vec<u8>) -> Result<OsString, String> {
+  String::from_utf8(bytes)
+    .map(OsString::from)
+    .map_err(|_| "Response file is not valid UTF-8".to_string())
+}
+
+
+/// Replace `@path` arguments with the arguments in the file at `path`.
+///
+/// Returns the expanded arguments along with, for each one, the index of
+/// the original argument it came from.
+pub(crate) fn expand(
+  args: &[OsString],
+  argidx: &[usize]
+) -> Result<(Vec<OsString>, Vec<usize>), RespFileErr> {
+  let mut exp = Expander {
+    args: Vec::new(),
+    argidx: Vec::new(),
+    posplit: false,
+    stack: Vec::new()
+  };
+
+  for (arg, idx) in args.iter().zip(argidx) {
+    exp.arg(arg.clone(), *idx, None)?;
+  }
+
+  Ok((exp.args, exp.argidx))
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -1,22 +1,93 @@
 //use std::cmp::Ordering;
-
-#[derive(Copy, Clone)]
+use std::cell::RefCell;
+use std::ffi::OsString;
+
+use crate::conv::{FromArgs, Typed, TypedProc};
+use crate::err::ErrKind;
+
+/// The number of arguments an option or positional argument takes.
+///
+/// A positional argument with a variable number of arguments only takes the
+/// arguments up to the next option.  Given `cp a -v b c`, a `SRC` spec with
+/// [`Nargs::OneOrMore`] takes only `a`, and `b` goes to the spec after it.
+#[derive(Copy, Clone, Default)]
 pub enum Nargs {
+  /// No arguments.
+  #[default]
   None,
+
+  /// A fixed number of arguments.
   Count(usize),
-  Remainder /*Optional,
-             *ZeroOrMore,
-             *OneOrMore */
-}
 
-impl Default for Nargs {
-  fn default() -> Self {
-    Nargs::None
-  }
+  /// Zero or one argument.
+  ///
+  /// Options only take the argument if it is attached to the option, as in
+  /// `--color=always` or `-calways`.
+  Optional,
+
+  /// Any number of arguments.
+  ZeroOrMore,
+
+  /// At least one argument.
+  OneOrMore,
+
+  /// Between `min` and `max` arguments, inclusive.
+  Range(usize, usize),
+
+  /// All the remaining arguments, regardless of whether they look like
+  /// options or not.
+  Remainder
 }
 
+/// Argument handler which receives its arguments as UTF-8 strings.
+///
+/// If any of the spec's arguments are not valid UTF-8 the parser will return
+/// an error instead of calling the handler.
 type Handler<C> = fn(spec: &Spec<C>, ctx: &mut C, args: &Vec<String>);
 
+/// Argument handler which receives its arguments as they were passed to the
+/// process, without any UTF-8 conversion.
+type OsHandler<C> = fn(spec: &Spec<C>, ctx: &mut C, args: &[OsString]);
+
+/// Argument handler which receives its arguments as UTF-8 strings and which
+/// can reject them.
+///
+/// Returning an error will cause the parser to fail with
+/// [`ErrKind::BadArg`](crate::ErrKind::BadArg).
+type TryHandler<C> =
+  fn(spec: &Spec<C>, ctx: &mut C, args: &Vec<String>) -> Result<(), String>;
+
+/// Argument handler which receives its arguments unaltered and which can
+/// reject them.
+type TryOsHandler<C> =
+  fn(spec: &Spec<C>, ctx: &mut C, args: &[OsString]) -> Result<(), String>;
+
+/// Boxed closure handler which receives its arguments as UTF-8 strings.
+type FnHandler<C> =
+  Box<dyn FnMut(&Spec<C>, &mut C, &Vec<String>) -> Result<(), String>>;
+
+/// Boxed closure handler which receives its arguments unaltered.
+type FnOsHandler<C> =
+  Box<dyn FnMut(&Spec<C>, &mut C, &[OsString]) -> Result<(), String>>;
+
+/// Boxed handler which receives its arguments converted to a value.
+type TypedHandler<C> = Box<dyn TypedProc<C>>;
+
+/// The argument handler attached to a [`Spec`].
+pub(crate) enum Proc<C> {
+  Str(Handler<C>),
+  OsStr(OsHandler<C>),
+  TryStr(TryHandler<C>),
+  TryOsStr(TryOsHandler<C>),
+  // Closures are called through a shared reference to the spec, so they need
+  // their own interior mutability.
+  Fn(RefCell<FnHandler<C>>),
+  FnOs(RefCell<FnOsHandler<C>>),
+  Typed(RefCell<TypedHandler<C>>),
+  // Only record the spec's matches.
+  Nop
+}
+
 
 /*
 type HandlerStr<C> = fn(spec: &Spec<C>, ctx: &mut C, arg: &str);
@@ -33,6 +104,26 @@ enum Handler {
 */
 
 
+/// An additional short or long option of a spec.
+#[derive(Clone)]
+struct Alias<T> {
+  name: T,
+  hidden: bool,
+  /// Message to warn with when the alias is used.
+  deprecated: Option<String>
+}
+
+impl<T> Alias<T> {
+  fn new(name: T, hidden: bool, deprecated: Option<&str>) -> Self {
+    Alias {
+      name,
+      hidden,
+      deprecated: deprecated.map(String::from)
+    }
+  }
+}
+
+
 /// Parser option/argument specification builder.
 //#[derive(Default)]
 pub struct Builder {
@@ -46,7 +137,38 @@ pub struct Builder {
   desc: Vec<String>,
 
   /// Whether to hide this entry from the help text.
-  hidden: bool
+  hidden: bool,
+
+  /// Whether sub-command parsers inherit this option.
+  global: bool,
+
+  /// Arguments to pass to the handler if the spec isn't matched.
+  default: Option<Vec<String>>,
+
+  /// Environment variable to read the argument(s) from if the spec isn't
+  /// matched.
+  env: Option<String>,
+
+  /// The values the argument(s) are restricted to.
+  choices: Option<Vec<String>>,
+
+  /// Whether the long option can be negated using a `no-` prefix.
+  negatable: bool,
+
+  /// Additional short options.
+  sopt_aliases: Vec<Alias<char>>,
+
+  /// Additional long options.
+  lopt_aliases: Vec<Alias<String>>,
+
+  /// Message to warn with when the spec is matched.
+  deprecated: Option<String>
+}
+
+impl Default for Builder {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Builder {
@@ -60,7 +182,15 @@ impl Builder {
       required: false,
       metanames: Vec::new(),
       desc: Vec::new(),
-      hidden: false
+      hidden: false,
+      global: false,
+      default: None,
+      env: None,
+      choices: None,
+      negatable: false,
+      sopt_aliases: Vec::new(),
+      lopt_aliases: Vec::new(),
+      deprecated: None
     }
   }
 
@@ -77,6 +207,57 @@ impl Builder {
     self
   }
 
+  /// Add a short option alias, which is listed in the help text.
+  ///
+  /// Aliases can only be added to specs which have a short or long option.
+  pub fn sopt_alias(&mut self, sopt: char) -> &mut Self {
+    self.sopt_aliases.push(Alias::new(sopt, false, None));
+    self
+  }
+
+  /// Add a short option alias, which is not listed in the help text.
+  pub fn hidden_sopt_alias(&mut self, sopt: char) -> &mut Self {
+    self.sopt_aliases.push(Alias::new(sopt, true, None));
+    self
+  }
+
+  /// Add a short option alias which causes a warning when it's used.  Like
+  /// hidden aliases, it's not listed in the help text.
+  ///
+  /// See [`deprecated()`](Self::deprecated) for how warnings are reported.
+  pub fn deprecated_sopt_alias(&mut self, sopt: char, msg: &str) -> &mut Self {
+    self.sopt_aliases.push(Alias::new(sopt, true, Some(msg)));
+    self
+  }
+
+  /// Add a long option alias, which is listed in the help text.
+  ///
+  /// Aliases can only be added to specs which have a short or long option.
+  pub fn lopt_alias(&mut self, lopt: &str) -> &mut Self {
+    self.lopt_aliases.push(Alias::new(String::from(lopt), false, None));
+    self
+  }
+
+  /// Add a long option alias, which is not listed in the help text.
+  ///
+  /// This is useful for keeping old spellings of renamed options working.
+  pub fn hidden_lopt_alias(&mut self, lopt: &str) -> &mut Self {
+    self.lopt_aliases.push(Alias::new(String::from(lopt), true, None));
+    self
+  }
+
+  /// Add a long option alias which causes a warning when it's used.  Like
+  /// hidden aliases, it's not listed in the help text.
+  ///
+  /// This is useful for nudging users of renamed options towards the new
+  /// spelling.  See [`deprecated()`](Self::deprecated) for how warnings are
+  /// reported.
+  pub fn deprecated_lopt_alias(&mut self, lopt: &str, msg: &str) -> &mut Self {
+    let alias = Alias::new(String::from(lopt), true, Some(msg));
+    self.lopt_aliases.push(alias);
+    self
+  }
+
   /// Assign argument specification a name.  This is required for positional
   /// arguments.
   pub fn name(&mut self, name: &str) -> &mut Self {
@@ -103,9 +284,9 @@ impl Builder {
   {
     self.metanames.clear();
     let nargs = match self.nargs {
-      Nargs::Remainder => 1,
+      Nargs::None => 0,
       Nargs::Count(n) => n,
-      _ => 0
+      _ => 1
     };
 
     let names = metanames
@@ -137,8 +318,11 @@ impl Builder {
     self
   }
 
-  /// Tell the parser that the argument must be processed.  This is only useful
-  /// for positional arguments.
+  /// Tell the parser that the argument must be processed.
+  ///
+  /// For options this means the option must appear on the command line at
+  /// least once.  Missing required options are reported by
+  /// [`Parser::validate()`](crate::Parser::validate).
   pub fn required(&mut self, req: bool) -> &mut Self {
     self.required = req;
     self
@@ -151,6 +335,90 @@ impl Builder {
     self
   }
 
+  /// Set the argument(s) the handler is called with if the option or
+  /// positional argument does not appear on the command line.
+  ///
+  /// Defaults are applied by [`Parser::parse()`](crate::Parser::parse) once
+  /// all arguments have been processed, in the order the specs were added.
+  /// An applied default does not count as the spec having been used, so it
+  /// neither satisfies [`required`](Builder::required) nor conflicts with
+  /// other options in a group.  The default is shown in the help text.
+  pub fn default_value<I, S>(&mut self, vals: I) -> &mut Self
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>
+  {
+    self.default =
+      Some(vals.into_iter().map(|x| String::from(x.as_ref())).collect());
+    self
+  }
+
+  /// Read the argument(s) from an environment variable if the option or
+  /// positional argument does not appear on the command line.
+  ///
+  /// The variable is only used if it is set and not empty.  Its value is
+  /// passed to the handler as a single argument; for specs which take more
+  /// than one argument it is split on ASCII whitespace.  Specs which take no
+  /// arguments are used if the value is `1`, `true`, `yes` or `on`, and not
+  /// if it's `0`, `false`, `no` or `off`, ignoring case; any other value is
+  /// reported as [`ErrKind::BadArg`](crate::ErrKind::BadArg).  A spec which
+  /// gets its arguments from the environment counts as having been used, and
+  /// takes precedence over a [`default_value`](Builder::default_value).
+  pub fn env(&mut self, var: &str) -> &mut Self {
+    self.env = Some(var.to_string());
+    self
+  }
+
+  /// Restrict the argument(s) to a set of values.
+  ///
+  /// The parser rejects any other value with
+  /// [`ErrKind::BadChoice`](crate::ErrKind::BadChoice) before the handler is
+  /// called.  The help text shows the choices, like `{auto,always,never}`,
+  /// instead of the metanames.
+  pub fn choices<I, S>(&mut self, choices: I) -> &mut Self
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>
+  {
+    self.choices =
+      Some(choices.into_iter().map(|x| String::from(x.as_ref())).collect());
+    self
+  }
+
+  /// Register both `--foo` and `--no-foo` for an option which takes no
+  /// arguments.
+  ///
+  /// The handler is called for either form, and can tell them apart using
+  /// [`Spec::is_negated()`](Spec::is_negated).  The forms can be used any
+  /// number of times; the handler is called each time, so the last one wins.
+  /// The help text shows the option as `--[no-]foo`.
+  pub fn negatable(&mut self, negatable: bool) -> &mut Self {
+    self.negatable = negatable;
+    self
+  }
+
+  /// Mark the spec as deprecated.  It still works as usual, but each time
+  /// it's matched the parser records a warning with `msg`, like
+  /// `"use --output instead"`.
+  ///
+  /// Warnings can be retrieved using
+  /// [`Parser::get_warnings()`](crate::Parser::get_warnings), or routed
+  /// elsewhere using
+  /// [`Parser::set_warning_sink()`](crate::Parser::set_warning_sink).  The
+  /// help text marks the spec as deprecated, unless it's hidden.
+  pub fn deprecated(&mut self, msg: &str) -> &mut Self {
+    self.deprecated = Some(String::from(msg));
+    self
+  }
+
+  /// Global options are inherited by sub-command parsers, so they can be
+  /// used both before and after the sub-command name.  An option defined by
+  /// the sub-command parser itself takes precedence over an inherited one.
+  pub fn global(&mut self, global: bool) -> &mut Self {
+    self.global = global;
+    self
+  }
+
   pub fn help<I, S>(&mut self, text: I) -> &mut Self
   where
     I: IntoIterator<Item = S>,
@@ -171,18 +439,119 @@ impl Builder {
     self
   }
 
+  /// Create a [`Spec`] whose handler receives its arguments as `String`s.
   pub fn build<C>(&self, proc: Handler<C>) -> Spec<C> {
+    self.build_proc(Proc::Str(proc))
+  }
+
+  /// Create a [`Spec`] whose handler receives its arguments as `OsString`s.
+  ///
+  /// Use this for arguments which may not be valid UTF-8, such as file paths.
+  pub fn build_os<C>(&self, proc: OsHandler<C>) -> Spec<C> {
+    self.build_proc(Proc::OsStr(proc))
+  }
+
+  /// Create a [`Spec`] whose handler receives its arguments as `String`s and
+  /// can report invalid arguments by returning an error message.
+  pub fn try_build<C>(&self, proc: TryHandler<C>) -> Spec<C> {
+    self.build_proc(Proc::TryStr(proc))
+  }
+
+  /// Create a [`Spec`] whose handler receives its arguments as `OsString`s
+  /// and can report invalid arguments by returning an error message.
+  pub fn try_build_os<C>(&self, proc: TryOsHandler<C>) -> Spec<C> {
+    self.build_proc(Proc::TryOsStr(proc))
+  }
+
+  /// Create a [`Spec`] whose handler is a closure, which may capture state.
+  ///
+  /// The closure receives its arguments as `String`s and can report invalid
+  /// arguments by returning an error message, just like handlers passed to
+  /// [`Builder::try_build()`](Builder::try_build).
+  pub fn build_fn<C, F>(&self, proc: F) -> Spec<C>
+  where
+    F: FnMut(&Spec<C>, &mut C, &Vec<String>) -> Result<(), String> + 'static
+  {
+    self.build_proc(Proc::Fn(RefCell::new(Box::new(proc))))
+  }
+
+  /// Create a [`Spec`] whose handler is a closure which receives its
+  /// arguments as `OsString`s.
+  pub fn build_fn_os<C, F>(&self, proc: F) -> Spec<C>
+  where
+    F: FnMut(&Spec<C>, &mut C, &[OsString]) -> Result<(), String> + 'static
+  {
+    self.build_proc(Proc::FnOs(RefCell::new(Box::new(proc))))
+  }
+
+  /// Create a [`Spec`] without a handler.
+  ///
+  /// What the parser sees of the spec is only recorded in its
+  /// [`Matches`](crate::Matches), which are retrieved using
+  /// [`Parser::get_matches()`](crate::Parser::get_matches).
+  pub fn build_match<C>(&self) -> Spec<C> {
+    self.build_proc(Proc::Nop)
+  }
+
+  /// Create a [`Spec`] whose handler receives its arguments converted to a
+  /// value of type `T`.
+  ///
+  /// The arguments are converted using [`FromArgs`] before the handler is
+  /// called; see its documentation for how the type relates to the number of
+  /// arguments.  Conversion failures are reported as
+  /// [`ErrKind::Conv`](crate::ErrKind::Conv), while errors returned by the
+  /// handler are reported as [`ErrKind::BadArg`](crate::ErrKind::BadArg).
+  ///
+  /// ```
+  /// use qargparser as arg;
+  ///
+  /// #[derive(Default)]
+  /// struct Ctx {
+  ///   port: u16
+  /// }
+  ///
+  /// let spec = arg::Builder::new()
+  ///   .lopt("port")
+  ///   .nargs(arg::Nargs::Count(1), &["PORT"])
+  ///   .build_typed(|_spec, ctx: &mut Ctx, port: u16| {
+  ///     ctx.port = port;
+  ///     Ok(())
+  ///   });
+  /// ```
+  pub fn build_typed<C, T, F>(&self, proc: F) -> Spec<C>
+  where
+    T: FromArgs + 'static,
+    F: FnMut(&Spec<C>, &mut C, T) -> Result<(), String> + 'static
+  {
+    let typed = Typed {
+      proc,
+      ty: std::marker::PhantomData
+    };
+    self.build_proc(Proc::Typed(RefCell::new(Box::new(typed))))
+  }
+
+  fn build_proc<C>(&self, proc: Proc<C>) -> Spec<C> {
     Spec {
       sopt: self.sopt,
       lopt: self.lopt.clone(),
       name: self.name.clone(),
-      nargs: self.nargs.clone(),
+      nargs: self.nargs,
       exit: self.exit,
       required: self.required,
       metanames: self.metanames.clone(),
       desc: self.desc.clone(),
       hidden: self.hidden,
-      proc
+      global: self.global,
+      default: self.default.clone(),
+      env: self.env.clone(),
+      choices: self.choices.clone(),
+      negatable: self.negatable,
+      sopt_aliases: self.sopt_aliases.clone(),
+      lopt_aliases: self.lopt_aliases.clone(),
+      deprecated: self.deprecated.clone(),
+      proc,
+      seen: 0,
+      negated: false
     }
   }
 }
@@ -205,10 +574,22 @@ pub struct Spec<C> {
   nargs: Nargs,
   pub(crate) exit: bool,
   required: bool,
-  metanames: Vec<String>,
+  pub(crate) metanames: Vec<String>,
   desc: Vec<String>,
   hidden: bool,
-  pub(crate) proc: Handler<C>
+  global: bool,
+  pub(crate) default: Option<Vec<String>>,
+  pub(crate) env: Option<String>,
+  pub(crate) choices: Option<Vec<String>>,
+  negatable: bool,
+  sopt_aliases: Vec<Alias<char>>,
+  lopt_aliases: Vec<Alias<String>>,
+  deprecated: Option<String>,
+  pub(crate) proc: Proc<C>,
+  /// Number of times the spec has been matched by a parser.
+  pub(crate) seen: usize,
+  /// Whether the spec was last matched by its negated long option.
+  pub(crate) negated: bool
 }
 
 
@@ -216,11 +597,7 @@ impl<C> Spec<C> {
   /// Return a boolean indicating whether this arg spec is configured to
   /// capture all the remaining arguments.
   pub fn is_capture_rest(&self) -> bool {
-    match self.nargs {
-      Nargs::None => false,
-      Nargs::Count(_n) => false,
-      Nargs::Remainder => true
-    }
+    matches!(self.nargs, Nargs::Remainder)
   }
 
   /// Return boolean indicating whether this arg spec will abort the parser.
@@ -255,34 +632,100 @@ impl<C> Spec<C> {
     self.hidden
   }
 
-  // ToDo: Don't panic!(), return Result instead.
-  pub fn get_nargs(&self) -> usize {
+  /// Return boolean indicating whether the long option can be negated using
+  /// a `no-` prefix.
+  pub fn is_negatable(&self) -> bool {
+    self.negatable
+  }
+
+  /// Return boolean indicating whether the handler is being called for the
+  /// negated form of the option, like `--no-foo`.
+  pub fn is_negated(&self) -> bool {
+    self.negated
+  }
+
+  /// Get the deprecation message, if the spec is deprecated.
+  pub fn get_deprecated(&self) -> Option<&str> {
+    self.deprecated.as_deref()
+  }
+
+  /// Return boolean indicating whether this option is inherited by
+  /// sub-command parsers.
+  pub fn is_global(&self) -> bool {
+    self.global
+  }
+
+  /// Get the number of arguments for a spec which takes a fixed number of
+  /// arguments.
+  ///
+  /// Use [`Spec::min_nargs()`](Spec::min_nargs) and
+  /// [`Spec::max_nargs()`](Spec::max_nargs) for specs with a variable number
+  /// of arguments.
+  ///
+  /// Returns [`ErrKind::BadContext`] for specs which don't take a fixed
+  /// number of arguments.
+  pub fn get_nargs(&self) -> Result<usize, ErrKind<C>> {
+    match self.nargs {
+      Nargs::None => Ok(0),
+      Nargs::Count(n) => Ok(n),
+      Nargs::Remainder => Err(ErrKind::BadContext(
+        "Can't get number of arguments for a capture-all spec.".to_string()
+      )),
+      _ => Err(ErrKind::BadContext(
+        "Can't get number of arguments for a variable count spec.".to_string()
+      ))
+    }
+  }
+
+  /// Get the argument count form of this spec.
+  pub fn nargs(&self) -> Nargs {
+    self.nargs
+  }
+
+  /// Get the minimum number of arguments this spec takes.
+  pub fn min_nargs(&self) -> usize {
     match self.nargs {
       Nargs::None => 0,
       Nargs::Count(n) => n,
-      Nargs::Remainder => {
-        panic!("Can't get number of arguments for a capture-all spec.");
-      }
+      Nargs::Optional => 0,
+      Nargs::ZeroOrMore => 0,
+      Nargs::OneOrMore => 1,
+      Nargs::Range(min, _) => min,
+      Nargs::Remainder => 0
     }
   }
-  pub fn req_args(&self) -> bool {
+
+  /// Get the maximum number of arguments this spec takes.  Returns `None` if
+  /// there's no upper limit.
+  pub fn max_nargs(&self) -> Option<usize> {
     match self.nargs {
-      Nargs::None => false,
-      Nargs::Count(n) => {
-        if n > 0 {
-          return true;
-        }
-        false
-      }
-      Nargs::Remainder => false
+      Nargs::None => Some(0),
+      Nargs::Count(n) => Some(n),
+      Nargs::Optional => Some(1),
+      Nargs::ZeroOrMore => None,
+      Nargs::OneOrMore => None,
+      Nargs::Range(_, max) => Some(max),
+      Nargs::Remainder => None
     }
   }
 
+  /// Return boolean indicating whether this spec requires at least one
+  /// argument.
+  pub fn req_args(&self) -> bool {
+    self.min_nargs() > 0
+  }
+
+  /// Return boolean indicating whether this spec can take any arguments at
+  /// all.
+  pub fn takes_args(&self) -> bool {
+    self.max_nargs() != Some(0)
+  }
+
   /// Generate a string representation of a short option.
   /// Does not include any arguments.
   ///
   /// Examples: "-h", "-f"
-  fn get_sopt_str(&self) -> Option<String> {
+  pub(crate) fn get_sopt_str(&self) -> Option<String> {
     if let Some(sopt) = self.sopt {
       let mut ret = '-'.to_string();
       let soptstr = sopt.to_string();
@@ -296,58 +739,196 @@ impl<C> Spec<C> {
   /// Does not include any arguments.
   ///
   /// Examples: "--help", "--file"
-  fn get_lopt_str(&self) -> Option<String> {
+  pub(crate) fn get_lopt_str(&self) -> Option<String> {
     if let Some(ref lopt) = self.lopt {
       let mut ret = "--".to_string();
-      ret.push_str(&lopt);
+      ret.push_str(lopt);
       return Some(ret);
     }
     None
   }
 
+  /// Return boolean indicating whether the spec has option aliases.
+  pub(crate) fn has_aliases(&self) -> bool {
+    !self.sopt_aliases.is_empty() || !self.lopt_aliases.is_empty()
+  }
+
+  /// Get the short option and its aliases.  Hidden aliases are only included
+  /// if `hidden` is set.
+  pub(crate) fn sopt_names(&self, hidden: bool) -> Vec<char> {
+    let aliases = self
+      .sopt_aliases
+      .iter()
+      .filter(|a| hidden || !a.hidden)
+      .map(|a| a.name);
+    self.sopt.into_iter().chain(aliases).collect()
+  }
+
+  /// Get the long option and its aliases, without the dashes.  Hidden
+  /// aliases are only included if `hidden` is set, and the negated forms of
+  /// negatable options if `negated` is set.
+  pub(crate) fn lopt_names(&self, hidden: bool, negated: bool) -> Vec<String> {
+    let aliases = self
+      .lopt_aliases
+      .iter()
+      .filter(|a| hidden || !a.hidden)
+      .map(|a| a.name.clone());
+    let names: Vec<String> =
+      self.lopt.iter().cloned().chain(aliases).collect();
+
+    if negated && self.negatable {
+      names
+        .into_iter()
+        .flat_map(|o| {
+          let neg = format!("no-{}", o);
+          [o, neg]
+        })
+        .collect()
+    } else {
+      names
+    }
+  }
+
+  /// Get the deprecation message for the short option `sopt`, which may be
+  /// an alias.
+  pub(crate) fn sopt_deprecation(&self, sopt: char) -> Option<&str> {
+    let alias = self.sopt_aliases.iter().find(|a| a.name == sopt);
+    alias
+      .and_then(|a| a.deprecated.as_deref())
+      .or(self.deprecated.as_deref())
+  }
+
+  /// Get the deprecation message for the long option `lopt`, without the
+  /// dashes, which may be an alias or the negated form of one.
+  pub(crate) fn lopt_deprecation(&self, lopt: &str) -> Option<&str> {
+    let alias = self.lopt_aliases.iter().find(|a| {
+      a.name == lopt
+        || (self.negatable && lopt.strip_prefix("no-") == Some(&a.name))
+    });
+    alias
+      .and_then(|a| a.deprecated.as_deref())
+      .or(self.deprecated.as_deref())
+  }
+
+  /// Return boolean indicating whether the short option is listed in the
+  /// help text.
+  pub(crate) fn is_visible_sopt(&self, sopt: char) -> bool {
+    !self.hidden && self.sopt_names(false).contains(&sopt)
+  }
+
+  /// Return boolean indicating whether the long option, without the dashes,
+  /// is listed in the help text.  Negated forms count as listed.
+  pub(crate) fn is_visible_lopt(&self, lopt: &str) -> bool {
+    !self.hidden && self.lopt_names(false, true).iter().any(|o| o == lopt)
+  }
+
+  /// Get the visible short and long options, including the dashes and the
+  /// negated forms of negatable options.
+  pub(crate) fn get_opt_strs(&self) -> Vec<String> {
+    let sopts = self.sopt_names(false).into_iter().map(|c| format!("-{}", c));
+    let lopts = self
+      .lopt_names(false, true)
+      .into_iter()
+      .map(|o| format!("--{}", o));
+    sopts.chain(lopts).collect()
+  }
+
   fn get_joined_meta_str(&self) -> Option<String> {
     match self.nargs {
       Nargs::None => None,
-      Nargs::Count(_n) => Some(self.metanames.join(" ")),
-      Nargs::Remainder => {
-        // ARG [ARG ...]
-        let metaname = if self.metanames.len() > 0 {
-          &self.metanames[0]
-        } else {
-          "ARG"
-        };
-        Some(metaname.to_string())
+      Nargs::Count(n) => match self.get_choices_str() {
+        Some(choices) => Some(vec![choices; n].join(" ")),
+        None => Some(self.metanames.join(" "))
+      },
+      // [ARG]
+      Nargs::Optional => Some(format!("[{}]", self.get_metaname())),
+      // [ARG ...]
+      Nargs::ZeroOrMore => Some(format!("[{} ...]", self.get_metaname())),
+      // ARG [ARG ...]
+      Nargs::OneOrMore => Some(format!("{0} [{0} ...]", self.get_metaname())),
+      // ARG ARG [ARG]
+      Nargs::Range(min, max) => {
+        let metaname = self.get_metaname();
+        let mut names = vec![metaname.clone(); min];
+        names.extend(vec![format!("[{}]", metaname); max.saturating_sub(min)]);
+        Some(names.join(" "))
       }
+      Nargs::Remainder => Some(self.get_metaname())
+    }
+  }
+
+  /// Get the first meta-name, for specs with a variable number of arguments.
+  /// Specs with a restricted set of choices use the choices instead.
+  fn get_metaname(&self) -> String {
+    if let Some(choices) = self.get_choices_str() {
+      return choices;
+    }
+    match self.metanames.first() {
+      Some(name) => name.clone(),
+      None => "ARG".to_string()
     }
   }
 
+  /// Get the choices as shown in the help text, like `{auto,always,never}`.
+  fn get_choices_str(&self) -> Option<String> {
+    self
+      .choices
+      .as_ref()
+      .map(|choices| format!("{{{}}}", choices.join(",")))
+  }
+
   /// Get a short option argument string.
   ///
   /// Example formats:
   /// - Some("-h")
   /// - Some("-f FILE")
   /// - Some("-p XCOORD YCOORD")
-  fn get_soptarg_str(&self) -> Option<String> {
-    if let Some(optstr) = self.get_sopt_str() {
-      let mut ret = optstr.to_owned();
-      if let Some(metastr) = self.get_joined_meta_str() {
-        ret.push_str(" ");
-        ret.push_str(&metastr);
+  /// - Some("-c[WHEN]")
+  pub(crate) fn get_soptarg_str(&self) -> Option<String> {
+    self.sopt.map(|sopt| self.fmt_soptarg(sopt))
+  }
+
+  /// Format a short option, which may be an alias, along with its arguments.
+  fn fmt_soptarg(&self, sopt: char) -> String {
+    let mut ret = format!("-{}", sopt);
+    if let Some(metastr) = self.get_joined_meta_str() {
+      // Optional arguments must be attached to the option.
+      if !matches!(self.nargs, Nargs::Optional) {
+        ret.push(' ');
       }
-      return Some(ret);
+      ret.push_str(&metastr);
     }
-    None
+    ret
   }
-  fn get_loptarg_str(&self) -> Option<String> {
-    if let Some(optstr) = self.get_lopt_str() {
-      let mut ret = optstr.to_owned();
-      if let Some(metastr) = self.get_joined_meta_str() {
-        ret.push_str(" ");
-        ret.push_str(&metastr);
-      }
-      return Some(ret);
+
+  /// Get a long option argument string.
+  ///
+  /// Example formats:
+  /// - Some("--help")
+  /// - Some("--file FILE")
+  /// - Some("--color[=WHEN]")
+  /// - Some("--[no-]color")
+  pub(crate) fn get_loptarg_str(&self) -> Option<String> {
+    self.lopt.as_deref().map(|lopt| self.fmt_loptarg(lopt, true))
+  }
+
+  /// Format a long option, which may be an alias, along with its arguments.
+  /// The help text shows negatable options as `--[no-]foo` if `negform` is
+  /// set, while errors refer to them as `--foo`.
+  fn fmt_loptarg(&self, lopt: &str, negform: bool) -> String {
+    let mut ret = "--".to_string();
+    if self.negatable && negform {
+      ret.push_str("[no-]");
     }
-    None
+    ret.push_str(lopt);
+    if let Nargs::Optional = self.nargs {
+      // Optional arguments must be attached to the option.
+      ret.push_str(&format!("[={}]", self.get_metaname()));
+    } else if let Some(metastr) = self.get_joined_meta_str() {
+      ret.push(' ');
+      ret.push_str(&metastr);
+    }
+    ret
   }
 
   #[cfg(test)]
@@ -361,9 +942,9 @@ impl<C> Spec<C> {
       }
       ret.push_str(&optstr);
       if self.required {
-        ret.push_str(">");
+        ret.push('>');
       } else {
-        ret.push_str("]");
+        ret.push(']');
       }
       return Some(ret);
     }
@@ -381,9 +962,9 @@ impl<C> Spec<C> {
       }
       ret.push_str(&optstr);
       if self.required {
-        ret.push_str(">");
+        ret.push('>');
       } else {
-        ret.push_str("]");
+        ret.push(']');
       }
       return Some(ret);
     }
@@ -394,19 +975,20 @@ impl<C> Spec<C> {
   // "-f FILE"
   // "--file FILE"
   pub fn get_opts_usage_str(&self) -> String {
+    // Visible aliases are listed after the option they alias.
     let mut args: Vec<String> = Vec::new();
-    if let Some(lstr) = self.get_soptarg_str() {
-      args.push(lstr);
+    for sopt in self.sopt_names(false) {
+      args.push(self.fmt_soptarg(sopt));
     }
-    if let Some(rstr) = self.get_loptarg_str() {
-      args.push(rstr);
+    for lopt in self.lopt_names(false, false) {
+      args.push(self.fmt_loptarg(&lopt, true));
     }
-    if args.len() == 0 {
+    if args.is_empty() {
       if let Some(posarg) = self.get_joined_meta_str() {
         args.push(posarg);
       }
     }
-    return args.join(", ");
+    args.join(", ")
   }
 
 
@@ -416,6 +998,16 @@ impl<C> Spec<C> {
   /// Required parameters are enclosed by '<' and '>' charcters.
   /// Optional parameters are enclossed by '[' and ']' characters.
   pub fn get_usage_str(&self) -> String {
+    self.fmt_usage(true)
+  }
+
+  /// Like [`get_usage_str()`](Self::get_usage_str), but refers to negatable
+  /// options by their plain long option, as errors do.
+  pub(crate) fn get_err_usage_str(&self) -> String {
+    self.fmt_usage(false)
+  }
+
+  fn fmt_usage(&self, negform: bool) -> String {
     let mut ret: String;
     if self.required {
       ret = '<'.to_string();
@@ -423,59 +1015,104 @@ impl<C> Spec<C> {
       ret = '['.to_string();
     }
 
-    if let Some(optstr) = self.get_loptarg_str() {
-      ret.push_str(&optstr);
+    if let Some(ref lopt) = self.lopt {
+      ret.push_str(&self.fmt_loptarg(lopt, negform));
     } else if let Some(optstr) = self.get_soptarg_str() {
       ret.push_str(&optstr);
-    } else if let Some(metastr) = self.get_joined_meta_str() {
+    } else {
+      // Positional argument specs without arguments are rejected by
+      // `Parser::add()`, but fall back to the metaname for them anyway.
+      let metastr = match self.get_joined_meta_str() {
+        Some(metastr) => metastr,
+        None => self.get_metaname()
+      };
       let s = match self.nargs {
-        Nargs::Count(_) => metastr.clone(),
+        // These are already enclosed in '[' and ']'.
+        Nargs::Optional | Nargs::ZeroOrMore | Nargs::Range(0, _) => {
+          return metastr;
+        }
         Nargs::Remainder => format!("{0} [{0} ...]", metastr),
-        _ => panic!(
-          "Attempted to print positional argument spec with no arguments"
-        )
+        _ => metastr
       };
       ret.push_str(&s);
-    } else {
-      panic!("Unexpected state");
     }
 
     if self.required {
-      ret.push_str(">");
+      ret.push('>');
     } else {
-      ret.push_str("]");
+      ret.push(']');
     }
 
-    return ret;
+    ret
   }
 
   pub fn get_help_title_str(&self) -> String {
+    // Visible aliases are listed after the option they alias.
     let mut args: Vec<String> = Vec::new();
-    if let Some(lstr) = self.get_soptarg_str() {
-      args.push(lstr);
+    for sopt in self.sopt_names(false) {
+      args.push(self.fmt_soptarg(sopt));
     }
-    if let Some(rstr) = self.get_loptarg_str() {
-      args.push(rstr);
+    for lopt in self.lopt_names(false, false) {
+      args.push(self.fmt_loptarg(&lopt, true));
     }
-    if args.len() == 0 {
-      if let Some(posarg) = self.get_joined_meta_str() {
-        let s = match self.nargs {
-          Nargs::Count(_) => posarg.clone(),
-          Nargs::Remainder => posarg.clone(),
-          _ => panic!(
-            "Attempted to print positional argument spec with no arguments"
-          )
-        };
-        args.push(s);
+    if args.is_empty() {
+      match self.get_joined_meta_str() {
+        Some(posarg) => args.push(posarg),
+        None => args.push(self.get_metaname())
       }
     }
-    return args.join(", ");
+    args.join(", ")
   }
 
 
   pub fn get_help_text(&self) -> &Vec<String> {
     &self.desc
   }
+
+  /// Get the default argument(s), if the spec has any.
+  pub fn get_default(&self) -> Option<&[String]> {
+    self.default.as_deref()
+  }
+
+  /// Get the values the argument(s) are restricted to, if any.
+  pub fn get_choices(&self) -> Option<&[String]> {
+    self.choices.as_deref()
+  }
+
+  /// Get the name of the environment variable the spec falls back to, if
+  /// any.
+  pub fn get_env(&self) -> Option<&str> {
+    self.env.as_deref()
+  }
+
+  /// Get the help text, with notes about the spec (like its default value)
+  /// appended to the last paragraph.
+  pub(crate) fn get_full_help_text(&self) -> Vec<String> {
+    let mut notes = Vec::new();
+    if let Some(ref msg) = self.deprecated {
+      notes.push(format!("[deprecated: {}]", msg));
+    }
+    if let Some(ref env) = self.env {
+      notes.push(format!("[env: {}]", env));
+    }
+    if let Some(ref default) = self.default {
+      notes.push(format!("(default: {})", default.join(" ")));
+    }
+
+    let mut desc = self.desc.clone();
+    if notes.is_empty() {
+      return desc;
+    }
+    let notes = notes.join(" ");
+    match desc.last_mut() {
+      Some(p) => {
+        p.push(' ');
+        p.push_str(&notes);
+      }
+      None => desc.push(notes)
+    }
+    desc
+  }
 }
 
 
@@ -520,6 +1157,7 @@ impl<C> PartialOrd for Spec<C> {
 
 
 #[cfg(test)]
+#[allow(dead_code)]
 mod tests {
   use std::collections::HashMap;
 
@@ -719,7 +1357,7 @@ fn test_help_switch() {
     .exit(true)
     .build(tests::help_proc);
 
-  assert_eq!(spec.is_exit(), true);
+  assert!(spec.is_exit());
 
   expect_opt_str(&spec.get_sopt_str(), "-h");
   expect_opt_str(&spec.get_lopt_str(), "--help");
@@ -741,10 +1379,10 @@ fn test_switch_noshort() {
     .exit(true)
     .build(tests::help_proc);
 
-  assert_eq!(spec.is_exit(), true);
+  assert!(spec.is_exit());
 
   let soptstr = spec.get_sopt_str();
-  assert_eq!(soptstr.is_none(), true);
+  assert!(soptstr.is_none());
 }
 
 
@@ -752,10 +1390,10 @@ fn test_switch_noshort() {
 fn test_switch_nolong() {
   let spec = Builder::new().sopt('h').exit(true).build(tests::help_proc);
 
-  assert_eq!(spec.is_exit(), true);
+  assert!(spec.is_exit());
 
   let loptstr = spec.get_lopt_str();
-  assert_eq!(loptstr.is_none(), true);
+  assert!(loptstr.is_none());
 }
 
 
@@ -820,6 +1458,47 @@ fn test_param_optarg() {
 }
 
 
+#[test]
+fn test_var_nargs() {
+  let spec = Builder::new()
+    .sopt('c')
+    .lopt("color")
+    .nargs(Nargs::Optional, &["WHEN"])
+    .build(tests::file_proc);
+
+  assert_eq!(spec.min_nargs(), 0);
+  assert_eq!(spec.max_nargs(), Some(1));
+  expect_opt_str(&spec.get_soptarg_str(), "-c[WHEN]");
+  expect_opt_str(&spec.get_loptarg_str(), "--color[=WHEN]");
+  assert_eq!(spec.get_usage_str(), "[--color[=WHEN]]");
+
+  let spec = Builder::new()
+    .name("files")
+    .nargs(Nargs::ZeroOrMore, &["FILE"])
+    .build(tests::args_proc);
+
+  assert_eq!(spec.max_nargs(), None);
+  assert_eq!(spec.get_usage_str(), "[FILE ...]");
+
+  let spec = Builder::new()
+    .name("files")
+    .required(true)
+    .nargs(Nargs::OneOrMore, &["FILE"])
+    .build(tests::args_proc);
+
+  assert_eq!(spec.min_nargs(), 1);
+  assert_eq!(spec.get_usage_str(), "<FILE [FILE ...]>");
+
+  let spec = Builder::new()
+    .lopt("point")
+    .nargs(Nargs::Range(2, 3), &["N"])
+    .build(tests::args_proc);
+
+  assert_eq!(spec.min_nargs(), 2);
+  assert_eq!(spec.max_nargs(), Some(3));
+  expect_opt_str(&spec.get_loptarg_str(), "--point N N [N]");
+}
+
 /*
 
 #[test]
@@ -948,4 +1627,31 @@ fn test_usage_posarg_req_str()
 */
 
 
+
+#[test]
+fn test_get_nargs() {
+  let spec = Builder::new()
+    .sopt('p')
+    .nargs(Nargs::Count(2), &["X", "Y"])
+    .build(tests::args_proc);
+  assert!(matches!(spec.get_nargs(), Ok(2)));
+
+  let spec = Builder::new()
+    .name("files")
+    .nargs(Nargs::OneOrMore, &["FILE"])
+    .build(tests::args_proc);
+  assert!(spec.get_nargs().is_err());
+
+  let spec = Builder::new()
+    .name("rest")
+    .nargs(Nargs::Remainder, &["ARG"])
+    .build(tests::args_proc);
+  assert!(spec.get_nargs().is_err());
+
+  // Positional arguments without arguments must not panic.
+  let spec = Builder::new().name("nothing").build(tests::args_proc);
+  assert_eq!(spec.get_usage_str(), "[ARG]");
+  assert_eq!(spec.get_help_title_str(), "ARG");
+}
+
 // vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
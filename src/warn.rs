@@ -0,0 +1,29 @@
+//! Warnings about deprecated options and positional arguments.
+
+use std::fmt;
+
+
+/// A deprecated option or positional argument which was used on the command
+/// line.
+#[derive(Clone, Debug)]
+pub struct Warning {
+  /// The option as it was given on the command line, like `--out`.  Long
+  /// options given as an unambiguous prefix are spelled out.  For positional
+  /// arguments this is the spec name, like `file`.
+  pub opt: String,
+
+  /// Index of the option, or of the first positional argument, in the
+  /// parser's argument list.
+  pub index: usize,
+
+  /// The deprecation message of the option, like `use --output instead`.
+  pub msg: String
+}
+
+impl fmt::Display for Warning {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_fmt(format_args!("'{}' is deprecated; {}", self.opt, self.msg))
+  }
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,214 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  help: bool,
+  color: Option<bool>,
+  width: usize
+}
+
+fn help_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.help = true;
+}
+
+fn color_proc(
+  spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.color = Some(!spec.is_negated());
+}
+
+fn width_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.width = args[0].parse().unwrap();
+}
+
+
+/// Add the options every test uses: `-h`, `--color` and `-w`, each with
+/// aliases.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('h')
+      .sopt_alias('?')
+      .lopt("help")
+      .help(["Show help."])
+      .build(help_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("color")
+      .hidden_lopt_alias("colour")
+      .negatable(true)
+      .help(["Use colors."])
+      .build(color_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('w')
+      .hidden_sopt_alias('W')
+      .lopt("width")
+      .lopt_alias("columns")
+      .nargs(arg::Nargs::Count(1), ["N"])
+      .help(["Output width."])
+      .build(width_proc)
+  )
+}
+
+
+#[test]
+fn aliases_work() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-?", "--colour", "-W", "40"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert!(ctx.help);
+  assert_eq!(ctx.color, Some(true));
+  assert_eq!(ctx.width, 40);
+
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--no-colour", "--columns=72"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  // Matches are recorded under the spec, not the spelling used.
+  assert_eq!(prsr.get_matches().value("width").unwrap(), "72");
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.color, Some(false));
+  assert_eq!(ctx.width, 72);
+
+  Ok(())
+}
+
+
+#[test]
+fn collisions() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+
+  let spec = arg::Builder::new().sopt('x').sopt_alias('?').build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::Collision(_))));
+
+  let spec = arg::Builder::new().lopt("colour").build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::Collision(_))));
+
+  // The negated form of an alias is taken too.
+  let spec = arg::Builder::new().lopt("no-colour").build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::Collision(_))));
+
+  let spec = arg::Builder::new()
+    .lopt("quiet")
+    .hidden_lopt_alias("quiet")
+    .build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::Collision(_))));
+
+  // Aliases must alias an option.
+  let spec = arg::Builder::new()
+    .name("file")
+    .lopt_alias("file")
+    .nargs(arg::Nargs::Count(1), ["FILE"])
+    .build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::BadContext(_))));
+
+  Ok(())
+}
+
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+
+  assert!(help.contains("  -h, -?, --help\n"));
+  assert!(help.contains("  --[no-]color\n"));
+  assert!(help.contains("  -w N, --width N, --columns N\n"));
+  assert!(!help.contains("colour"));
+  assert!(!help.contains("-W"));
+
+  Ok(())
+}
+
+
+#[test]
+fn suggestions() -> Result<(), Box<dyn std::error::Error>> {
+  // Hidden aliases are neither suggested nor abbreviated.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--colou"], MyContext::default());
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::UnknownOpt(e)) => {
+      assert_eq!(e.suggestion.as_deref(), Some("--color"));
+    }
+    _ => panic!("Unknown option not reported")
+  }
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--colo"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.set_prefix_matching(true);
+  assert!(prsr.parse().is_ok());
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--col"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.set_prefix_matching(true);
+  match prsr.parse() {
+    Err(arg::ErrKind::Ambiguous(e)) => {
+      assert_eq!(e.candidates, vec!["--color", "--columns"]);
+    }
+    _ => panic!("Ambiguous option not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn completion() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Bash, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script
+    .contains("opts='-h -? --help --color --no-color -w --width --columns'"));
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Fish, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script.contains("-s 'h' -s '?' -l 'help'"));
+  assert!(!script.contains("colour"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,138 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  color: String,
+  color_calls: usize
+}
+
+fn color_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.color = args[0].clone();
+  ctx.color_calls += 1;
+}
+
+
+fn mkcolor(default: &str) -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('c')
+    .lopt("color")
+    .nargs(arg::Nargs::Count(1), ["WHEN"])
+    .choices(["auto", "always", "never"])
+    .default_value([default])
+    .help(["When to use colors."])
+    .build(color_proc)
+}
+
+
+#[test]
+fn valid_choice() -> Result<(), Box<dyn std::error::Error>> {
+  let cases: [(&[&str], &str); 3] = [
+    (&["--color=never"], "never"),
+    (&["-calways"], "always"),
+    (&[], "auto")
+  ];
+  for (args, color) in cases {
+    let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+    prsr.add(mkcolor("auto"))?;
+    prsr.parse()?;
+    assert_eq!(prsr.get_ctx().color, color);
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn bad_choice() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--color", "sometimes"],
+    MyContext::default()
+  );
+  prsr.add(mkcolor("auto"))?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::BadChoice(e)) => {
+      assert_eq!(e.arg, "sometimes");
+      assert_eq!(e.choices, vec!["auto", "always", "never"]);
+      assert_eq!(
+        e.to_string(),
+        "'sometimes'; expected one of auto, always, never"
+      );
+    }
+    _ => panic!("Invalid choice not reported")
+  }
+
+  // The handler is not called for invalid choices.
+  assert_eq!(prsr.get_ctx().color_calls, 0);
+
+  Ok(())
+}
+
+
+#[test]
+fn bad_default() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkcolor("yes"))?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::BadChoice(e)) => assert_eq!(e.arg, "yes"),
+    _ => panic!("Invalid default not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkcolor("auto"))?;
+
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+
+  assert!(help.contains("Usage: tool [--color {auto,always,never}]"));
+  assert!(help
+    .contains("-c {auto,always,never}, --color {auto,always,never}"));
+
+  Ok(())
+}
+
+
+#[test]
+fn completion() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkcolor("auto"))?;
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Bash, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script.contains("'tool,-c'|'tool,--color')"));
+  assert!(script.contains("compgen -W 'auto always never'"));
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Zsh, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script.contains(":WHEN:(auto always never)'"));
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Fish, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script.contains(" -x -a 'auto always never'"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
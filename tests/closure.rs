@@ -0,0 +1,79 @@
+use std::cell::Cell;
+use std::ffi::OsString;
+use std::rc::Rc;
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  user: String,
+  host: String,
+  files: Vec<OsString>
+}
+
+/// Generic spec factory which stores the option's argument in a context
+/// field.
+fn mkstore(
+  lopt: &str,
+  field: fn(&mut MyContext) -> &mut String
+) -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .lopt(lopt)
+    .nargs(arg::Nargs::Count(1), ["VALUE"])
+    .build_fn(move |_spec, ctx: &mut MyContext, args| {
+      *field(ctx) = args[0].clone();
+      Ok(())
+    })
+}
+
+#[test]
+fn capture_state() -> Result<(), Box<dyn std::error::Error>> {
+  let counter = Rc::new(Cell::new(0));
+
+  let cnt = Rc::clone(&counter);
+  let verbose_spec = arg::Builder::new()
+    .sopt('v')
+    .build_fn(move |_spec, _ctx: &mut MyContext, _args| {
+      cnt.set(cnt.get() + 1);
+      Ok(())
+    });
+
+  let limit = 2;
+  let file_spec = arg::Builder::new()
+    .name("file")
+    .nargs(arg::Nargs::Remainder, ["FILE"])
+    .build_fn_os(move |_spec, ctx: &mut MyContext, args| {
+      if args.len() > limit {
+        return Err(format!("At most {} files are allowed.", limit));
+      }
+      ctx.files.extend_from_slice(args);
+      Ok(())
+    });
+
+  let ctx = MyContext {
+    ..Default::default()
+  };
+  let mut prsr = arg::Parser::from_args(
+    "closure",
+    ["-vv", "--user", "jane", "--host=example.org", "-v", "a", "b"],
+    ctx
+  );
+
+  prsr.add(verbose_spec)?;
+  prsr.add(mkstore("user", |ctx| &mut ctx.user))?;
+  prsr.add(mkstore("host", |ctx| &mut ctx.host))?;
+  prsr.add(file_spec)?;
+
+  prsr.parse()?;
+
+  assert_eq!(counter.get(), 3);
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.user, "jane");
+  assert_eq!(ctx.host, "example.org");
+  assert_eq!(ctx.files, vec![OsString::from("a"), OsString::from("b")]);
+
+  Ok(())
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,184 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool,
+  file: String,
+  force: bool
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn file_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.file = args[0].clone();
+}
+
+fn force_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.force = true;
+}
+
+
+fn mkparser() -> Result<arg::Parser<MyContext>, Box<dyn std::error::Error>>
+{
+  let args: [&str; 0] = [];
+  let mut prsr =
+    arg::Parser::from_args("/usr/bin/tool", args, MyContext::default());
+
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .global(true)
+      .help(["Be verbose."])
+      .build(verbose_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('f')
+      .lopt("file")
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .help(["Read [input] from FILE.", "Second paragraph."])
+      .build(file_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("secret")
+      .hidden(true)
+      .help(["Hidden option."])
+      .build(verbose_proc)
+  )?;
+
+  let mut sub = arg::Parser::new_subcmd();
+  sub.add(
+    arg::Builder::new()
+      .lopt("force")
+      .help(["Don't ask."])
+      .build(force_proc)
+  )?;
+  prsr.add_subcmd("clone", ["Clone a repository."], sub)?;
+
+  Ok(prsr)
+}
+
+fn gen(shell: arg::Shell) -> Result<String, Box<dyn std::error::Error>> {
+  let prsr = mkparser()?;
+  let mut buf = Vec::new();
+  prsr.completion(shell, &mut buf);
+  Ok(String::from_utf8(buf)?)
+}
+
+
+#[test]
+fn bash() -> Result<(), Box<dyn std::error::Error>> {
+  let script = gen(arg::Shell::Bash)?;
+
+  assert!(script.contains("complete -F _tool"));
+  assert!(script.contains("opts='-v --verbose -f --file'"));
+  assert!(script.contains("argopts='-f --file'"));
+  assert!(script.contains("'tool,clone') cmd='tool clone' ;;"));
+  // Global option is inherited by the sub-command.
+  assert!(script.contains("opts='--force -v --verbose'"));
+  assert!(!script.contains("--secret"));
+
+  Ok(())
+}
+
+
+#[test]
+fn zsh() -> Result<(), Box<dyn std::error::Error>> {
+  let script = gen(arg::Shell::Zsh)?;
+
+  assert!(script.starts_with("#compdef tool\n"));
+  assert!(script.contains("'(-v --verbose)'{-v,--verbose}'[Be verbose.]'"));
+  assert!(script.contains(
+    "'(-f --file)'{-f,--file}'[Read \\[input\\] from FILE.]:FILE:_files'"
+  ));
+  assert!(script.contains("'clone:Clone a repository.'"));
+  assert!(script.contains("_tool__clone() {"));
+  assert!(script.contains("'--force[Don'\\''t ask.]'"));
+  assert!(!script.contains("--secret"));
+  assert!(!script.contains("Second paragraph"));
+
+  Ok(())
+}
+
+
+#[test]
+fn fish() -> Result<(), Box<dyn std::error::Error>> {
+  let script = gen(arg::Shell::Fish)?;
+
+  assert!(script
+    .contains("complete -c 'tool' -s 'v' -l 'verbose' -d 'Be verbose.'\n"));
+  assert!(script.contains(
+    "complete -c 'tool' -n '__fish_use_subcommand' -s 'f' -l 'file' -r"
+  ));
+  assert!(script.contains(
+    "complete -c 'tool' -n '__fish_use_subcommand' -f -a 'clone' \
+     -d 'Clone a repository.'\n"
+  ));
+  assert!(script.contains(
+    "complete -c 'tool' -n '__fish_seen_subcommand_from clone' \
+     -l 'force' -d 'Don\\'t ask.'\n"
+  ));
+  assert!(!script.contains("secret"));
+
+  Ok(())
+}
+
+
+#[test]
+fn fish_nested() -> Result<(), Box<dyn std::error::Error>> {
+  let mut child = arg::Parser::new_subcmd();
+  child.add(
+    arg::Builder::new()
+      .lopt("force")
+      .help(["Don't ask."])
+      .build(force_proc)
+  )?;
+  let mut parent = arg::Parser::new_subcmd();
+  parent.add(arg::Builder::new().lopt("dry-run").build(verbose_proc))?;
+  parent.add_subcmd("add", ["Add a remote."], child)?;
+  let mut prsr = mkparser()?;
+  prsr.add_subcmd("remote", ["Manage remotes."], parent)?;
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Fish, &mut buf);
+  let script = String::from_utf8(buf)?;
+
+  // Conditions name the full path of the sub-command, and a parent's
+  // options are not offered once one of its sub-commands has been given.
+  assert!(script.contains(
+    "complete -c 'tool' -n '__fish_seen_subcommand_from remote; \
+     and not __fish_seen_subcommand_from add' -l 'dry-run'\n"
+  ));
+  assert!(script.contains(
+    "complete -c 'tool' -n '__fish_seen_subcommand_from remote; \
+     and not __fish_seen_subcommand_from add' -f -a 'add' \
+     -d 'Add a remote.'\n"
+  ));
+  assert!(script.contains(
+    "complete -c 'tool' -n '__fish_seen_subcommand_from remote; \
+     and __fish_seen_subcommand_from add' -l 'force' -d 'Don\\'t ask.'\n"
+  ));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,145 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  port: u16,
+  port_calls: usize,
+  color: String,
+  output: String
+}
+
+fn port_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) -> Result<(), String> {
+  ctx.port = args[0].parse::<u16>().map_err(|e| e.to_string())?;
+  ctx.port_calls += 1;
+  Ok(())
+}
+
+fn color_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.color = args[0].clone();
+}
+
+fn output_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.output = args[0].clone();
+}
+
+
+fn mkport(port: &str) -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('p')
+    .lopt("port")
+    .nargs(arg::Nargs::Count(1), ["PORT"])
+    .default_value([port])
+    .help(["Port to listen on."])
+    .try_build(port_proc)
+}
+
+fn mkcolor() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .lopt("color")
+    .nargs(arg::Nargs::Count(1), ["WHEN"])
+    .default_value(["auto"])
+    .build(color_proc)
+}
+
+fn mkoutput() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .name("output")
+    .nargs(arg::Nargs::Count(1), ["OUTPUT"])
+    .default_value(["-"])
+    .help(["Where to write output."])
+    .build(output_proc)
+}
+
+
+#[test]
+fn defaults_applied() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkport("8080"))?;
+  prsr.add(mkcolor())?;
+  prsr.add(mkoutput())?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.port, 8080);
+  assert_eq!(ctx.port_calls, 1);
+  assert_eq!(ctx.color, "auto");
+  assert_eq!(ctx.output, "-");
+
+  Ok(())
+}
+
+
+#[test]
+fn defaults_overridden() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--port", "22", "--color=never", "out"],
+    MyContext::default()
+  );
+  prsr.add(mkport("8080"))?;
+  prsr.add(mkcolor())?;
+  prsr.add(mkoutput())?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.port, 22);
+  assert_eq!(ctx.port_calls, 1);
+  assert_eq!(ctx.color, "never");
+  assert_eq!(ctx.output, "out");
+
+  Ok(())
+}
+
+
+#[test]
+fn bad_default() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkport("http"))?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::BadArg(_)) => {}
+    _ => panic!("Invalid default not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkport("8080"))?;
+  prsr.add(mkcolor())?;
+  prsr.add(mkoutput())?;
+
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+
+  // Sentences are separated by two spaces in the help output.
+  assert!(help.contains("Port to listen on.  (default: 8080)"));
+  assert!(help.contains("--color WHEN\n    (default: auto)"));
+  assert!(help.contains("Where to write output.  (default: -)"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
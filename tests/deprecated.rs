@@ -0,0 +1,229 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  output: String,
+  color: Option<bool>,
+  quiet: bool
+}
+
+fn output_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.output = args[0].clone();
+}
+
+fn color_proc(
+  spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.color = Some(!spec.is_negated());
+}
+
+fn quiet_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.quiet = true;
+}
+
+
+/// Add the options every test uses, with deprecated spellings.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('o')
+      .lopt("output")
+      .deprecated_lopt_alias("out", "use --output instead")
+      .deprecated_sopt_alias('O', "use -o instead")
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .help(["Output file."])
+      .build(output_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("color")
+      .deprecated_lopt_alias("colour", "use --color instead")
+      .negatable(true)
+      .help(["Use colors."])
+      .build(color_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('q')
+      .lopt("silent")
+      .deprecated("use --log-level=0 instead")
+      .help(["Be quiet."])
+      .build(quiet_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("legacy")
+      .deprecated("has no effect")
+      .hidden(true)
+      .build_match()
+  )
+}
+
+
+#[test]
+fn recorded() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--out", "a.txt", "-q", "--no-colour"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  // The options still work.
+  let warnings = prsr.get_warnings().to_vec();
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.output, "a.txt");
+  assert_eq!(ctx.color, Some(false));
+  assert!(ctx.quiet);
+
+  let opts: Vec<&str> = warnings.iter().map(|w| w.opt.as_str()).collect();
+  assert_eq!(opts, ["--out", "-q", "--no-colour"]);
+  assert_eq!(warnings[0].index, 0);
+  assert_eq!(warnings[1].index, 2);
+  assert_eq!(
+    warnings[0].to_string(),
+    "'--out' is deprecated; use --output instead"
+  );
+  assert_eq!(warnings[1].msg, "use --log-level=0 instead");
+
+  // Bundled options are reported individually.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["-qOb.txt"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  let opts: Vec<&str> =
+    prsr.get_warnings().iter().map(|w| w.opt.as_str()).collect();
+  assert_eq!(opts, ["-q", "-O"]);
+  assert!(prsr.get_warnings().iter().all(|w| w.index == 0));
+
+  // The new spellings don't warn.
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-o", "c.txt", "--output=d.txt", "--color"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert!(prsr.get_warnings().is_empty());
+
+  Ok(())
+}
+
+
+#[test]
+fn sink() -> Result<(), Box<dyn std::error::Error>> {
+  let seen = Rc::new(RefCell::new(Vec::new()));
+  let sink = Rc::clone(&seen);
+
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--silent", "--legacy"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.set_warning_sink(move |w| sink.borrow_mut().push(w.to_string()));
+  prsr.parse()?;
+
+  assert!(prsr.get_warnings().is_empty());
+  assert_eq!(
+    *seen.borrow(),
+    [
+      "'--silent' is deprecated; use --log-level=0 instead",
+      "'--legacy' is deprecated; has no effect"
+    ]
+  );
+
+  Ok(())
+}
+
+
+#[test]
+fn subcmd() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-q", "run", "--out", "x"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  let mut sub = arg::Parser::new_subcmd();
+  sub.add(
+    arg::Builder::new()
+      .lopt("output")
+      .deprecated_lopt_alias("out", "use --output instead")
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .build(output_proc)
+  )?;
+  prsr.add_subcmd("run", ["Run things."], sub)?;
+  prsr.parse()?;
+
+  // Warnings from sub-commands are recorded by the top level parser.
+  let warnings = prsr.get_warnings();
+  assert_eq!(warnings.len(), 2);
+  assert_eq!(warnings[1].opt, "--out");
+  assert_eq!(warnings[1].index, 2);
+
+  Ok(())
+}
+
+
+#[test]
+fn posarg() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["-q", "a", "b"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.add(
+    arg::Builder::new()
+      .name("input")
+      .nargs(arg::Nargs::OneOrMore, ["INPUT"])
+      .deprecated("use --input instead")
+      .build_match()
+  )?;
+  prsr.parse()?;
+
+  let warnings = prsr.get_warnings();
+  assert_eq!(warnings.len(), 2);
+  assert_eq!(warnings[1].opt, "input");
+  assert_eq!(warnings[1].index, 1);
+  assert_eq!(warnings[1].msg, "use --input instead");
+
+  Ok(())
+}
+
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+
+  assert!(help.contains("Be quiet.  [deprecated: use --log-level=0 instead]"));
+  assert!(!help.contains("--legacy"));
+  assert!(!help.contains("--out "));
+  assert!(!help.contains("colour"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,144 @@
+#![cfg(feature = "derive")]
+
+use std::path::PathBuf;
+
+use qargparser::{self as arg, QArgs};
+
+#[derive(Default, QArgs)]
+struct MyContext {
+  /// Show this help.
+  #[qarg(sopt = 'h', lopt, exit)]
+  help: bool,
+
+  #[qarg(sopt = 'v', lopt, help = "Be verbose.")]
+  verbose: bool,
+
+  /// Port to listen on.
+  ///
+  /// Defaults to 80.
+  #[qarg(sopt = 'p', lopt, meta = "PORT", required)]
+  port: Option<u16>,
+
+  #[qarg(lopt = "map", meta = "NAME", meta = "PATH")]
+  mapping: Option<(String, PathBuf)>,
+
+  #[qarg(sopt = 'I', meta = "DIR")]
+  include: Vec<PathBuf>,
+
+  #[qarg(lopt, nargs = "?", meta = "LEVEL", hidden)]
+  debug_level: Option<u8>,
+
+  /// Files to serve.
+  #[qarg(name = "file", meta = "FILE")]
+  files: Vec<String>,
+
+  not_an_arg: usize
+}
+
+
+#[test]
+fn parsed() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = MyContext::from_args(
+    "tool",
+    [
+      "-v",
+      "--port",
+      "80",
+      "--map",
+      "www",
+      "/srv/www",
+      "-Ia",
+      "-I",
+      "b",
+      "--debug-level=2",
+      "index.html",
+      "style.css"
+    ]
+  )?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert!(!ctx.help);
+  assert!(ctx.verbose);
+  assert_eq!(ctx.port, Some(80));
+  assert_eq!(
+    ctx.mapping,
+    Some((String::from("www"), PathBuf::from("/srv/www")))
+  );
+  assert_eq!(ctx.include, vec![PathBuf::from("a"), PathBuf::from("b")]);
+  assert_eq!(ctx.debug_level, Some(2));
+  assert_eq!(ctx.files, vec!["index.html", "style.css"]);
+  assert_eq!(ctx.not_an_arg, 0);
+
+  Ok(())
+}
+
+
+#[test]
+fn optional_value() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    MyContext::from_args("tool", ["--port", "80", "--debug-level"])?;
+  prsr.parse()?;
+  assert!(prsr.get_matches().contains("debug_level"));
+  assert_eq!(prsr.get_ctx().debug_level, None);
+
+  let mut prsr =
+    MyContext::from_args("tool", ["--port", "80", "--debug-level=3"])?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().debug_level, Some(3));
+
+  Ok(())
+}
+
+
+#[test]
+fn exit() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = MyContext::from_args("tool", ["--help", "--port", "http"])?;
+  assert!(prsr.parse()?.is_some());
+  assert!(prsr.get_ctx().help);
+
+  Ok(())
+}
+
+
+#[test]
+fn errors() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = MyContext::from_args("tool", ["--port", "http"])?;
+  match prsr.parse() {
+    Err(arg::ErrKind::Conv(e)) => assert_eq!(e.arg, Some("http".into())),
+    _ => panic!("Conversion error not reported")
+  }
+
+  let mut prsr = MyContext::from_args("tool", ["-v"])?;
+  match prsr.parse() {
+    Err(arg::ErrKind::MissOpt(e)) => assert_eq!(e.opts, vec!["<--port PORT>"]),
+    _ => panic!("Missing required option not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+  let prsr = MyContext::from_args("tool", Vec::<&str>::new())?;
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+
+  assert!(help.contains("-h, --help"));
+  assert!(help.contains("Show this help."));
+  assert!(help.contains("Be verbose."));
+  assert!(help.contains("-p PORT, --port PORT"));
+  assert!(help.contains("--map NAME PATH"));
+  assert!(help.contains("Port to listen on."));
+  assert!(help.contains("Defaults to 80."));
+  assert!(help.contains("Files to serve."));
+  assert!(!help.contains("--debug-level"));
+
+  assert!(prsr.get_matches().get("port").is_none());
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
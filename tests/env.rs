@@ -0,0 +1,211 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  port: String,
+  verbose: bool,
+  point: Vec<String>
+}
+
+fn port_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.port = args[0].clone();
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn point_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.point = args.clone();
+}
+
+
+/// Each test uses its own set of variables, since tests run in parallel.
+fn mkport(prefix: &str) -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .lopt("port")
+    .nargs(arg::Nargs::Count(1), ["PORT"])
+    .env(&format!("{}_PORT", prefix))
+    .default_value(["80"])
+    .required(true)
+    .help(["Port to listen on."])
+    .build(port_proc)
+}
+
+fn mkverbose(prefix: &str) -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .lopt("verbose")
+    .env(&format!("{}_VERBOSE", prefix))
+    .build(verbose_proc)
+}
+
+fn mkpoint(prefix: &str) -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .lopt("point")
+    .nargs(arg::Nargs::Count(2), ["X", "Y"])
+    .env(&format!("{}_POINT", prefix))
+    .build(point_proc)
+}
+
+
+#[test]
+fn from_env() -> Result<(), Box<dyn std::error::Error>> {
+  std::env::set_var("QARGTEST_ENV1_PORT", "8080");
+  std::env::set_var("QARGTEST_ENV1_VERBOSE", "1");
+  std::env::set_var("QARGTEST_ENV1_POINT", "3 4");
+
+  // The environment satisfies the required option.
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkport("QARGTEST_ENV1"))?;
+  prsr.add(mkverbose("QARGTEST_ENV1"))?;
+  prsr.add(mkpoint("QARGTEST_ENV1"))?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.port, "8080");
+  assert!(ctx.verbose);
+  assert_eq!(ctx.point, vec!["3", "4"]);
+
+  Ok(())
+}
+
+
+#[test]
+fn argv_wins() -> Result<(), Box<dyn std::error::Error>> {
+  std::env::set_var("QARGTEST_ENV2_PORT", "8080");
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--port", "22"], MyContext::default());
+  prsr.add(mkport("QARGTEST_ENV2"))?;
+  prsr.add(mkverbose("QARGTEST_ENV2"))?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.port, "22");
+  assert!(!ctx.verbose);
+
+  Ok(())
+}
+
+
+#[test]
+fn unset_or_empty() -> Result<(), Box<dyn std::error::Error>> {
+  std::env::set_var("QARGTEST_ENV3_PORT", "");
+
+  // Neither the environment nor the default satisfy the required option.
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkport("QARGTEST_ENV3"))?;
+  match prsr.parse() {
+    Err(arg::ErrKind::MissOpt(_)) => {}
+    _ => panic!("Missing required option not reported")
+  }
+  assert_eq!(prsr.get_ctx().port, "80");
+
+  Ok(())
+}
+
+
+#[test]
+fn bad_count() -> Result<(), Box<dyn std::error::Error>> {
+  std::env::set_var("QARGTEST_ENV4_PORT", "1");
+  std::env::set_var("QARGTEST_ENV4_POINT", "1 2 3");
+
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkport("QARGTEST_ENV4"))?;
+  prsr.add(mkpoint("QARGTEST_ENV4"))?;
+  match prsr.parse() {
+    Err(arg::ErrKind::BadArg(e)) => {
+      assert_eq!(e.args, vec!["1 2 3"]);
+      assert!(e.msg.contains("QARGTEST_ENV4_POINT"));
+    }
+    _ => panic!("Bad argument count not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn flag_values() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+
+  for (val, verbose) in [("on", true), ("TRUE", true), ("0", false)] {
+    std::env::set_var("QARGTEST_ENV6_VERBOSE", val);
+    let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+    prsr.add(mkverbose("QARGTEST_ENV6"))?;
+    prsr.parse()?;
+    assert_eq!(prsr.get_ctx().verbose, verbose);
+    assert_eq!(prsr.get_matches().contains("verbose"), verbose);
+  }
+
+  std::env::set_var("QARGTEST_ENV6_VERBOSE", "maybe");
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkverbose("QARGTEST_ENV6"))?;
+  match prsr.parse() {
+    Err(arg::ErrKind::BadArg(e)) => {
+      assert_eq!(e.args, vec!["maybe"]);
+      assert!(e.msg.contains("QARGTEST_ENV6_VERBOSE"));
+    }
+    _ => panic!("Bad boolean not reported")
+  }
+
+  Ok(())
+}
+
+
+#[cfg(unix)]
+#[test]
+fn non_utf8() -> Result<(), Box<dyn std::error::Error>> {
+  use std::ffi::OsStr;
+  use std::os::unix::ffi::OsStrExt;
+
+  std::env::set_var("QARGTEST_ENV7_POINT", OsStr::from_bytes(b"\xff 2"));
+
+  // Values are split without being converted to UTF-8 first, so the
+  // handler's conversion fails instead of seeing a replacement character.
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkpoint("QARGTEST_ENV7"))?;
+  assert!(matches!(prsr.parse(), Err(arg::ErrKind::InvalidUtf8(_))));
+
+  Ok(())
+}
+
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkport("QARGTEST_ENV5"))?;
+  prsr.add(mkverbose("QARGTEST_ENV5"))?;
+
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+
+  assert!(help
+    .contains("Port to listen on.  [env: QARGTEST_ENV5_PORT] (default: 80)"));
+  assert!(help.contains("[env: QARGTEST_ENV5_VERBOSE]"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
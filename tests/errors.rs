@@ -0,0 +1,173 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool,
+  points: Vec<String>
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn point_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.points = args.clone();
+}
+
+
+fn mkverbose() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('v')
+    .lopt("verbose")
+    .build(verbose_proc)
+}
+
+fn mkpoint() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .name("point")
+    .nargs(arg::Nargs::Count(2), ["X", "Y"])
+    .build(point_proc)
+}
+
+
+#[test]
+fn parse_unknown_opt() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--verbse", "-v"], MyContext::default());
+  prsr.add(mkverbose())?;
+  prsr.add(mkpoint())?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::UnknownOpt(_)) => {}
+    _ => panic!("Unknown option not reported")
+  }
+
+  // Parsing stopped at the unknown option.
+  assert!(!prsr.get_ctx().verbose);
+
+  Ok(())
+}
+
+
+#[test]
+fn parse_missing_posarg() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args("tool", ["1"], MyContext::default());
+  prsr.add(mkverbose())?;
+  prsr.add(mkpoint())?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::MissArg(_)) => {}
+    _ => panic!("Missing positional argument not reported")
+  }
+  assert!(prsr.get_ctx().points.is_empty());
+
+  Ok(())
+}
+
+
+#[test]
+fn parse_extra_posarg() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["1", "2", "3"], MyContext::default());
+  prsr.add(mkverbose())?;
+  prsr.add(mkpoint())?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::MissSpec(_)) => {}
+    _ => panic!("Superfluous positional argument not reported")
+  }
+  assert_eq!(prsr.get_ctx().points, vec!["1", "2"]);
+
+  Ok(())
+}
+
+
+#[test]
+fn iterator_err() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-v", "--bad", "-v"],
+    MyContext::default()
+  );
+  prsr.add(mkverbose())?;
+  prsr.add(mkpoint())?;
+
+  let n = prsr.by_ref().count();
+  assert_eq!(n, 1);
+  assert!(prsr.did_fail());
+  match prsr.get_err() {
+    Some(arg::ErrKind::UnknownOpt(e)) => assert_eq!(e.opt, "--bad"),
+    _ => panic!("Error not stored")
+  }
+
+  // The iterator stays exhausted after an error.
+  assert!(Iterator::next(&mut prsr).is_none());
+
+  assert!(matches!(prsr.take_err(), Some(arg::ErrKind::UnknownOpt(_))));
+  assert!(!prsr.did_fail());
+
+  Ok(())
+}
+
+
+#[test]
+fn bundled_unknown_sopt() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args("tool", ["-vx"], MyContext::default());
+  prsr.add(mkverbose())?;
+  prsr.add(mkpoint())?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::UnknownOpt(e)) => assert_eq!(e.opt, "-x"),
+    _ => panic!("Unknown bundled option not reported")
+  }
+  assert!(!prsr.get_ctx().verbose);
+
+  Ok(())
+}
+
+
+#[test]
+fn bad_specs() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.add(mkverbose())?;
+  prsr.add(mkpoint())?;
+
+  // A positional argument which takes no arguments.
+  let spec = arg::Builder::new().name("nothing").build(verbose_proc);
+  match prsr.add(spec) {
+    Err(arg::ErrKind::BadContext(_)) => {}
+    _ => panic!("Positional argument without arguments accepted")
+  }
+
+  let spec = arg::Builder::new()
+    .lopt("range")
+    .nargs(arg::Nargs::Range(3, 1), ["N"])
+    .build(point_proc);
+  match prsr.add(spec) {
+    Err(arg::ErrKind::BadContext(_)) => {}
+    _ => panic!("Invalid range accepted")
+  }
+
+  // Rejected specs must not be registered.
+  let spec = arg::Builder::new()
+    .lopt("range")
+    .nargs(arg::Nargs::Range(1, 3), ["N"])
+    .build(point_proc);
+  assert!(prsr.add(spec).is_ok());
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
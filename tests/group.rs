@@ -0,0 +1,203 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  format: String,
+  file: String,
+  stdin: bool,
+  user: String,
+  password: String
+}
+
+fn format_proc(
+  spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.format = spec.get_usage_str();
+}
+
+fn file_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.file = args[0].clone();
+}
+
+fn stdin_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.stdin = true;
+}
+
+fn user_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.user = args[0].clone();
+}
+
+fn password_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.password = args[0].clone();
+}
+
+
+/// Add the output, input and login options and their groups.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  for fmt in &["json", "yaml", "table"] {
+    prsr.add(arg::Builder::new().lopt(fmt).build(format_proc))?;
+  }
+  prsr.add(
+    arg::Builder::new()
+      .sopt('f')
+      .lopt("file")
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .build(file_proc)
+  )?;
+  prsr.add(arg::Builder::new().lopt("stdin").build(stdin_proc))?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("user")
+      .nargs(arg::Nargs::Count(1), ["USER"])
+      .build(user_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("password")
+      .nargs(arg::Nargs::Count(1), ["PASSWORD"])
+      .build(password_proc)
+  )?;
+
+  prsr.add_group(arg::Group::AtMostOne, &["--json", "--yaml", "--table"])?;
+  prsr.add_group(arg::Group::ExactlyOne, &["-f", "--stdin"])?;
+  prsr.add_group(arg::Group::Requires, &["--user", "--password"])?;
+
+  Ok(())
+}
+
+fn group_err(
+  args: &[&str]
+) -> Result<arg::GroupErr<MyContext>, arg::ErrKind<MyContext>> {
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::Group(e)) => Ok(e),
+    _ => panic!("Group violation not reported")
+  }
+}
+
+
+#[test]
+fn valid() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--yaml", "--stdin", "--user", "u", "--password", "p"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["-f", "x"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  Ok(())
+}
+
+
+#[test]
+fn at_most_one() -> Result<(), Box<dyn std::error::Error>> {
+  let e = group_err(&["--stdin", "--table", "--json"])?;
+  assert_eq!(e.kind, arg::Group::AtMostOne);
+  assert_eq!(e.opts, vec!["--json", "--table"]);
+  assert_eq!(e.to_string(), "--json can't be used together with --table");
+
+  Ok(())
+}
+
+
+#[test]
+fn exactly_one() -> Result<(), Box<dyn std::error::Error>> {
+  let e = group_err(&["--json"])?;
+  assert_eq!(e.kind, arg::Group::ExactlyOne);
+  assert_eq!(e.opts, vec!["--file", "--stdin"]);
+
+  let e = group_err(&["--stdin", "--file=x"])?;
+  assert_eq!(e.kind, arg::Group::ExactlyOne);
+  assert_eq!(e.opts, vec!["--file", "--stdin"]);
+
+  Ok(())
+}
+
+
+#[test]
+fn requires() -> Result<(), Box<dyn std::error::Error>> {
+  let e = group_err(&["--stdin", "--user", "u"])?;
+  assert_eq!(e.kind, arg::Group::Requires);
+  assert_eq!(e.to_string(), "--user requires --password");
+
+  // The dependency only goes one way.
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--stdin", "--password", "p"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  assert!(prsr.parse().is_ok());
+
+  Ok(())
+}
+
+
+#[test]
+fn bad_group() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  match prsr.add_group(arg::Group::AtMostOne, &["--json", "--xml"]) {
+    Err(arg::ErrKind::MissSpec(_)) => {}
+    _ => panic!("Unknown option in group accepted")
+  }
+  match prsr.add_group(arg::Group::AtMostOne, &["--json"]) {
+    Err(arg::ErrKind::BadContext(_)) => {}
+    _ => panic!("Single option group accepted")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn usage() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  let mut buf = Vec::new();
+  prsr.print_usage(&mut buf);
+  let usage = String::from_utf8(buf)?;
+  let usage = usage.split_whitespace().collect::<Vec<_>>().join(" ");
+
+  assert_eq!(
+    usage,
+    "Usage: tool [--json | --yaml | --table] <--file FILE | --stdin> \
+     [--user USER] [--password PASSWORD]"
+  );
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
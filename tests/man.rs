@@ -0,0 +1,122 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool,
+  file: String
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn file_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.file = args[0].clone();
+}
+
+
+#[test]
+fn man_page() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr =
+    arg::Parser::from_args("/usr/bin/tool", args, MyContext::default());
+
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .help(["Be verbose."])
+      .build(verbose_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("secret")
+      .hidden(true)
+      .help(["Hidden option."])
+      .build(verbose_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .name("file")
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .help(["File to process.", ".dot at line start."])
+      .build(file_proc)
+  )?;
+
+  prsr.set_tophelp(["Process files.", "A longer description."]);
+  prsr.set_bottomhelp([
+    "Some notes.",
+    "Examples:",
+    "tool foo.txt",
+    "See also:",
+    "cat(1)"
+  ]);
+
+  let mut buf = Vec::new();
+  prsr.man("1", &mut buf);
+  let page = String::from_utf8(buf)?;
+
+  let expected = "\
+.TH \"TOOL\" \"1\"
+.SH NAME
+tool \\- Process files.
+.SH SYNOPSIS
+.B tool
+[\\-\\-verbose] [FILE]
+.SH DESCRIPTION
+Process files.
+.PP
+A longer description.
+.SH OPTIONS
+.TP
+.B \\-v, \\-\\-verbose
+Be verbose.
+.SH ARGUMENTS
+.TP
+.B FILE
+File to process.
+.PP
+\\&.dot at line start.
+.SH \"NOTES\"
+Some notes.
+.SH \"EXAMPLES\"
+tool foo.txt
+.SH \"SEE ALSO\"
+cat(1)
+";
+  assert_eq!(page, expected);
+
+  Ok(())
+}
+
+
+#[test]
+fn man_subcmds() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+
+  prsr.add_subcmd("init", ["Create things."], arg::Parser::new_subcmd())?;
+
+  let mut buf = Vec::new();
+  prsr.man("8", &mut buf);
+  let page = String::from_utf8(buf)?;
+
+  assert!(page.starts_with(".TH \"TOOL\" \"8\"\n.SH NAME\ntool\n"));
+  assert!(page.contains(".B tool\n<COMMAND> [ARGS ...]\n"));
+  assert!(page.contains(".SH COMMANDS\n.TP\n.B init\nCreate things.\n"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
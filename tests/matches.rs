@@ -0,0 +1,115 @@
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: usize
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose += 1;
+}
+
+
+/// Add a mix of options and positional arguments, all but one without a
+/// handler.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .build(verbose_proc)
+  )?;
+  prsr.add(arg::Builder::new().sopt('q').build_match())?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('I')
+      .name("include")
+      .nargs(arg::Nargs::Count(1), ["DIR"])
+      .build_match()
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("jobs")
+      .nargs(arg::Nargs::Count(1), ["N"])
+      .default_value(["1"])
+      .build_match()
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .name("files")
+      .nargs(arg::Nargs::ZeroOrMore, ["FILE"])
+      .build_match()
+  )?;
+
+  Ok(())
+}
+
+
+#[test]
+fn recorded() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-v", "-I", "a", "--verbose", "-Ib", "x", "y", "-vq"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  let m = prsr.get_matches();
+
+  // Specs with handlers are recorded too.
+  assert!(m.contains("verbose"));
+  assert_eq!(m.count("verbose"), 3);
+  assert_eq!(prsr.get_ctx().verbose, 3);
+
+  assert_eq!(m.count("q"), 1);
+
+  assert_eq!(m.count("include"), 2);
+  assert_eq!(m.values("include"), ["a", "b"]);
+  assert_eq!(m.value("include").unwrap(), "b");
+  assert_eq!(m.indices("include"), [1, 4]);
+
+  assert_eq!(m.values("files"), ["x", "y"]);
+  assert_eq!(m.indices("files"), [5]);
+  assert_eq!(m.indices("verbose"), [0, 3, 7]);
+  assert_eq!(m.indices("q"), [7]);
+
+  Ok(())
+}
+
+
+#[test]
+fn defaults() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  let m = prsr.get_matches();
+
+  assert!(!m.contains("jobs"));
+  assert_eq!(m.value("jobs").unwrap(), "1");
+  assert!(m.indices("jobs").is_empty());
+
+  assert!(!m.contains("verbose"));
+  assert!(m.get("verbose").is_none());
+  assert!(m.values("files").is_empty());
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--jobs", "4"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  let m = prsr.get_matches();
+
+  assert!(m.contains("jobs"));
+  assert_eq!(m.values("jobs"), ["4"]);
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
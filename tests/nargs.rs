@@ -0,0 +1,295 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  color: Option<String>,
+  defines: Vec<String>,
+  coords: Vec<String>,
+  srcs: Vec<String>,
+  dest: String,
+  extra: Vec<String>
+}
+
+fn color_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.color = Some(args.first().cloned().unwrap_or_else(|| "auto".into()));
+}
+
+fn define_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.defines.extend_from_slice(args);
+}
+
+fn coord_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.coords = args.clone();
+}
+
+fn src_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.srcs = args.clone();
+}
+
+fn dest_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.dest = args[0].clone();
+}
+
+fn extra_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.extra = args.clone();
+}
+
+
+fn mkcolor() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('c')
+    .lopt("color")
+    .nargs(arg::Nargs::Optional, ["WHEN"])
+    .build(color_proc)
+}
+
+fn mkdefine() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('D')
+    .lopt("define")
+    .nargs(arg::Nargs::OneOrMore, ["NAME"])
+    .build(define_proc)
+}
+
+fn mkcoord() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .lopt("coord")
+    .nargs(arg::Nargs::Range(2, 3), ["N"])
+    .build(coord_proc)
+}
+
+fn mkextra() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .name("extra")
+    .nargs(arg::Nargs::ZeroOrMore, ["EXTRA"])
+    .build(extra_proc)
+}
+
+/// Add the options and `SRC... DEST` positional arguments of a `cp`-like
+/// tool.
+fn add_cp_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(mkcolor())?;
+  prsr.add(mkdefine())?;
+  prsr.add(mkcoord())?;
+  prsr.add(
+    arg::Builder::new()
+      .name("src")
+      .required(true)
+      .nargs(arg::Nargs::OneOrMore, ["SRC"])
+      .build(src_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .name("dest")
+      .required(true)
+      .nargs(arg::Nargs::Count(1), ["DEST"])
+      .build(dest_proc)
+  )
+}
+
+
+#[test]
+fn optional_optarg() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("cp", ["--color", "a", "b"], MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.color.as_deref(), Some("auto"));
+  assert_eq!(ctx.srcs, vec!["a"]);
+  assert_eq!(ctx.dest, "b");
+
+  let mut prsr = arg::Parser::from_args(
+    "cp",
+    ["--color=never", "a", "b"],
+    MyContext::default()
+  );
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color.as_deref(), Some("never"));
+
+  let mut prsr =
+    arg::Parser::from_args("cp", ["-calways", "a", "b"], MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color.as_deref(), Some("always"));
+
+  let mut prsr =
+    arg::Parser::from_args("cp", ["-c", "a", "b"], MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color.as_deref(), Some("auto"));
+
+  Ok(())
+}
+
+#[test]
+fn bundled_optional_optarg() -> Result<(), Box<dyn std::error::Error>> {
+  let cases: [(&[&str], &str, &[&str]); 3] = [
+    (&["-vcalways"], "always", &[]),
+    (&["-vc"], "auto", &[]),
+    (&["-vc", "always"], "auto", &["always"])
+  ];
+  for (args, color, extra) in cases {
+    let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+    prsr.add(arg::Builder::new().sopt('v').build_match())?;
+    prsr.add(mkcolor())?;
+    prsr.add(mkextra())?;
+    prsr.parse()?;
+
+    assert_eq!(prsr.get_matches().count("v"), 1);
+    let ctx = prsr.into_ctx();
+    assert_eq!(ctx.color.as_deref(), Some(color));
+    assert_eq!(ctx.extra, extra);
+  }
+
+  Ok(())
+}
+
+#[test]
+fn variable_optargs() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "cp",
+    ["-D", "x", "y", "--coord", "1", "2", "3", "4", "a", "b"],
+    MyContext::default()
+  );
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.defines, vec!["x", "y"]);
+  assert_eq!(ctx.coords, vec!["1", "2", "3"]);
+  assert_eq!(ctx.srcs, vec!["4", "a"]);
+  assert_eq!(ctx.dest, "b");
+
+  let mut prsr = arg::Parser::from_args(
+    "cp",
+    ["-Dx", "-D", "y", "z", "-c", "a", "b"],
+    MyContext::default()
+  );
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().defines, vec!["x", "y", "z"]);
+
+  let mut prsr =
+    arg::Parser::from_args("cp", ["--coord", "1"], MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  match prsr.next() {
+    Err(arg::ErrKind::MissArg(_)) => {}
+    _ => panic!("Expected a missing argument error")
+  }
+
+  Ok(())
+}
+
+#[test]
+fn greedy_posargs() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("cp", ["a", "b", "c", "d"], MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.srcs, vec!["a", "b", "c"]);
+  assert_eq!(ctx.dest, "d");
+
+  let mut prsr =
+    arg::Parser::from_args("cp", ["a", "b"], MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.srcs, vec!["a"]);
+  assert_eq!(ctx.dest, "b");
+
+  // Variable argument counts stop at the next option.
+  let mut prsr =
+    arg::Parser::from_args("cp", ["a", "-c", "b", "c"], MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  assert!(matches!(prsr.parse(), Err(arg::ErrKind::MissSpec(_))));
+
+  Ok(())
+}
+
+#[test]
+fn optional_posargs() -> Result<(), Box<dyn std::error::Error>> {
+  // The only argument is needed by the spec which follows.
+  let mut prsr = arg::Parser::from_args("opt", ["x"], MyContext::default());
+  prsr.add(mkextra())?;
+  prsr.add(
+    arg::Builder::new()
+      .name("dest")
+      .nargs(arg::Nargs::Count(1), ["DEST"])
+      .build(dest_proc)
+  )?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert!(ctx.extra.is_empty());
+  assert_eq!(ctx.dest, "x");
+
+  let mut prsr =
+    arg::Parser::from_args("opt", ["x", "y", "z"], MyContext::default());
+  prsr.add(mkextra())?;
+  prsr.add(
+    arg::Builder::new()
+      .name("dest")
+      .nargs(arg::Nargs::Count(1), ["DEST"])
+      .build(dest_proc)
+  )?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.extra, vec!["x", "y"]);
+  assert_eq!(ctx.dest, "z");
+
+  Ok(())
+}
+
+#[test]
+fn nargs_usage() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("cp", args, MyContext::default());
+  add_cp_specs(&mut prsr)?;
+  let mut buf = Vec::new();
+  prsr.print_usage(&mut buf);
+  let usage = String::from_utf8(buf)?;
+  assert!(usage.contains("[--color[=WHEN]]"));
+  assert!(usage.contains("[--define NAME [NAME ...]]"));
+  assert!(usage.contains("[--coord N N [N]]"));
+  assert!(usage.contains("<SRC [SRC ...]>"));
+  assert!(usage.contains("<DEST>"));
+
+  let mut buf = Vec::new();
+  prsr.print_opts(&mut buf);
+  let opts = String::from_utf8(buf)?;
+  assert!(opts.contains("-c[WHEN], --color[=WHEN]"));
+
+  Ok(())
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,198 @@
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  color: Option<bool>
+}
+
+fn color_proc(
+  spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.color = Some(!spec.is_negated());
+}
+
+
+/// Add the negatable `--color` and `--pager` options.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('c')
+      .lopt("color")
+      .negatable(true)
+      .help(["Use colors."])
+      .build(color_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("pager")
+      .negatable(true)
+      .build_match()
+  )?;
+
+  Ok(())
+}
+
+
+#[test]
+fn forms() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color, None);
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--color"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color, Some(true));
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--no-color"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color, Some(false));
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--no-color", "-c"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color, Some(true));
+
+  Ok(())
+}
+
+
+#[test]
+fn last_wins() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--color", "--no-pager", "--no-color", "--pager"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color, Some(false));
+
+  let m = prsr.get_matches();
+  assert_eq!(m.count("pager"), 2);
+  assert!(!m.get("pager").unwrap().negated);
+  assert!(m.get("color").unwrap().negated);
+
+  Ok(())
+}
+
+
+#[test]
+fn invalid() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+
+  // Negatable options need a long option and can't take arguments.
+  let spec = arg::Builder::new().sopt('x').negatable(true).build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::BadContext(_))));
+
+  let spec = arg::Builder::new()
+    .lopt("width")
+    .nargs(arg::Nargs::Count(1), ["N"])
+    .negatable(true)
+    .build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::BadContext(_))));
+
+  // The negated form collides with other long options.
+  let spec = arg::Builder::new().lopt("no-color").build_match();
+  assert!(matches!(prsr.add(spec), Err(arg::ErrKind::Collision(_))));
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--no-color=yes"], MyContext::default());
+  add_specs(&mut prsr)?;
+  assert!(prsr.parse().is_err());
+
+  Ok(())
+}
+
+
+#[test]
+fn errors() -> Result<(), Box<dyn std::error::Error>> {
+  // Errors name the plain long option, not the help text's `--[no-]` form.
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--color", "--no-pager"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.add_group(arg::Group::AtMostOne, &["--color", "--pager"])?;
+  match prsr.parse() {
+    Err(arg::ErrKind::Group(e)) => {
+      assert_eq!(e.opts, vec!["--color", "--pager"]);
+      assert_eq!(e.msg, "--color can't be used together with --pager");
+    }
+    _ => panic!("Group conflict not reported")
+  }
+
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("fast")
+      .negatable(true)
+      .required(true)
+      .build_match()
+  )?;
+  match prsr.parse() {
+    Err(arg::ErrKind::MissOpt(e)) => assert_eq!(e.opts, vec!["<--fast>"]),
+    _ => panic!("Missing required option not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+
+  assert!(help.contains("Usage: tool [--[no-]color] [--[no-]pager]"));
+  assert!(help.contains("  -c, --[no-]color\n    Use colors."));
+  assert!(!help.contains("--no-color"));
+
+  Ok(())
+}
+
+
+#[test]
+fn completion() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Bash, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script.contains("opts='-c --color --no-color --pager --no-pager'"));
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Zsh, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script.contains("'(-c --color --no-color)'{-c,--color,--no-color}"));
+
+  let mut buf = Vec::new();
+  prsr.completion(arg::Shell::Fish, &mut buf);
+  let script = String::from_utf8(buf)?;
+  assert!(script.contains("-l 'color' -l 'no-color'"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
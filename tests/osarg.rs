@@ -0,0 +1,97 @@
+#![cfg(unix)]
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  file: PathBuf,
+  name: String
+}
+
+fn file_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &[OsString]
+) {
+  ctx.file = PathBuf::from(&args[0]);
+}
+
+fn name_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.name = args[0].clone();
+}
+
+fn mkfile() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('f')
+    .lopt("file")
+    .nargs(arg::Nargs::Count(1), ["FILE"])
+    .build_os(file_proc)
+}
+
+fn mkname() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('n')
+    .lopt("name")
+    .nargs(arg::Nargs::Count(1), ["NAME"])
+    .build(name_proc)
+}
+
+#[test]
+fn non_utf8_osarg() -> Result<(), Box<dyn std::error::Error>> {
+  let fname = OsStr::from_bytes(b"caf\xe9.txt");
+
+  let mut prsr = arg::Parser::from_args(
+    "osarg",
+    [OsStr::new("-f"), fname],
+    MyContext::default()
+  );
+  prsr.add(mkfile())?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().file.as_os_str(), fname);
+
+  let fname = OsStr::from_bytes(b"--file=caf\xe9.txt");
+  let mut prsr =
+    arg::Parser::from_args("osarg", [fname], MyContext::default());
+  prsr.add(mkfile())?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().file.as_os_str().as_bytes(), b"caf\xe9.txt");
+
+  let fname = OsStr::from_bytes(b"-fcaf\xe9.txt");
+  let mut prsr =
+    arg::Parser::from_args("osarg", [fname], MyContext::default());
+  prsr.add(mkfile())?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().file.as_os_str().as_bytes(), b"caf\xe9.txt");
+
+  Ok(())
+}
+
+#[test]
+fn non_utf8_strarg() -> Result<(), Box<dyn std::error::Error>> {
+  let name = OsStr::from_bytes(b"caf\xe9");
+
+  let mut prsr = arg::Parser::from_args(
+    "osarg",
+    [OsStr::new("--name"), name],
+    MyContext::default()
+  );
+  prsr.add(mkname())?;
+  match prsr.next() {
+    Err(arg::ErrKind::InvalidUtf8(_)) => {}
+    _ => panic!("Expected an invalid UTF-8 error")
+  }
+
+  Ok(())
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,246 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool,
+  file: String,
+  files: Vec<String>,
+  color: Option<String>
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn file_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.file = args[0].clone();
+}
+
+fn color_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.color = Some(args.first().cloned().unwrap_or_else(|| "auto".into()));
+}
+
+fn files_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.files.extend(args.iter().cloned());
+}
+
+
+/// Add `-v` and `-f`.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .build(verbose_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('f')
+      .lopt("file")
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .build(file_proc)
+  )?;
+
+  Ok(())
+}
+
+fn unrecognized(prsr: &arg::Parser<MyContext>) -> Vec<&str> {
+  prsr
+    .get_unrecognized()
+    .iter()
+    .map(|a| a.to_str().unwrap())
+    .collect()
+}
+
+
+#[test]
+fn collected() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    [
+      "--jobs",
+      "4",
+      "-v",
+      "--color=never",
+      "-x",
+      "--file",
+      "a.txt",
+      "--dry-run"
+    ],
+    MyContext::default()
+  );
+  prsr.set_passthrough(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  assert_eq!(
+    unrecognized(&prsr),
+    ["--jobs", "4", "--color=never", "-x", "--dry-run"]
+  );
+  let ctx = prsr.into_ctx();
+  assert!(ctx.verbose);
+  assert_eq!(ctx.file, "a.txt");
+
+  // Passing through is opt-in.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--jobs"], MyContext::default());
+  add_specs(&mut prsr)?;
+  assert!(matches!(prsr.parse(), Err(arg::ErrKind::UnknownOpt(_))));
+
+  Ok(())
+}
+
+
+#[test]
+fn bundled() -> Result<(), Box<dyn std::error::Error>> {
+  // Known options are split off the front of the bundle.
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-vxfz", "b.txt", "-fc.txt"],
+    MyContext::default()
+  );
+  prsr.set_passthrough(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  assert_eq!(unrecognized(&prsr), ["-xfz", "b.txt"]);
+  let ctx = prsr.into_ctx();
+  assert!(ctx.verbose);
+  assert_eq!(ctx.file, "c.txt");
+
+  // An argument attached to a known option is not a bundle.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["-fxyz", "-yv"], MyContext::default());
+  prsr.set_passthrough(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(unrecognized(&prsr), ["-yv"]);
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.file, "xyz");
+  assert!(!ctx.verbose);
+
+  Ok(())
+}
+
+
+#[test]
+fn bundled_optarg() -> Result<(), Box<dyn std::error::Error>> {
+  // -v and -c are known, and -c takes an optional attached argument.
+  let cases: [(&[&str], Option<&str>, &[&str]); 5] = [
+    (&["-vcx"], Some("x"), &[]),
+    (&["-vc"], Some("auto"), &[]),
+    (&["-vzcx"], None, &["-zcx"]),
+    (&["-zvc"], None, &["-zvc"]),
+    (&["-vcx", "-yv"], Some("x"), &["-yv"])
+  ];
+  for (args, color, unknown) in cases {
+    let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+    prsr.set_passthrough(true);
+    add_specs(&mut prsr)?;
+    prsr.add(
+      arg::Builder::new()
+        .sopt('c')
+        .nargs(arg::Nargs::Optional, ["WHEN"])
+        .build(color_proc)
+    )?;
+    prsr.parse()?;
+
+    assert_eq!(unrecognized(&prsr), unknown);
+    let ctx = prsr.into_ctx();
+    assert_eq!(ctx.verbose, args[0].starts_with("-v"));
+    assert_eq!(ctx.color.as_deref(), color);
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn posargs() -> Result<(), Box<dyn std::error::Error>> {
+  // Positional argument specs take precedence over unknown options.
+  let cases: [(&[&str], &[&str]); 2] = [
+    (&["--jobs", "4", "x", "-v"], &["4", "x"]),
+    (&["-v", "--jobs", "--", "4"], &["4"])
+  ];
+  for (args, files) in cases {
+    let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+    prsr.set_passthrough(true);
+    add_specs(&mut prsr)?;
+    prsr.add(
+      arg::Builder::new()
+        .name("files")
+        .nargs(arg::Nargs::ZeroOrMore, ["FILE"])
+        .build(files_proc)
+    )?;
+    prsr.parse()?;
+
+    assert_eq!(unrecognized(&prsr), ["--jobs"]);
+    let ctx = prsr.into_ctx();
+    assert!(ctx.verbose);
+    assert_eq!(ctx.files, files);
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn subcmd() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--top", "run", "-v", "--sub=1"],
+    MyContext::default()
+  );
+  prsr.set_passthrough(true);
+  add_specs(&mut prsr)?;
+  let mut sub = arg::Parser::new_subcmd();
+  sub.set_passthrough(true);
+  sub.add(arg::Builder::new().sopt('v').build(verbose_proc))?;
+  prsr.add_subcmd("run", ["Run things."], sub)?;
+  prsr.parse()?;
+
+  // Unknown options of the sub-command are collected by the top level
+  // parser.
+  assert_eq!(prsr.get_subcmd(), Some("run"));
+  assert_eq!(unrecognized(&prsr), ["--top", "--sub=1"]);
+  assert!(prsr.get_ctx().verbose);
+
+  Ok(())
+}
+
+
+#[test]
+fn many() -> Result<(), Box<dyn std::error::Error>> {
+  // Each unknown option must not cost a stack frame.
+  let args = vec!["-x"; 20000];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.set_passthrough(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_unrecognized().len(), 20000);
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
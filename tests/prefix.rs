@@ -0,0 +1,152 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool,
+  version: bool,
+  color: Option<bool>,
+  file: String
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn version_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.version = true;
+}
+
+fn color_proc(
+  spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.color = Some(!spec.is_negated());
+}
+
+fn file_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.file = args[0].clone();
+}
+
+
+/// Add long options sharing prefixes, and a hidden one.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(arg::Builder::new().lopt("verbose").build(verbose_proc))?;
+  prsr.add(arg::Builder::new().lopt("version").build(version_proc))?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("color")
+      .negatable(true)
+      .build(color_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("file")
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .build(file_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("debug-internals")
+      .hidden(true)
+      .build_match()
+  )?;
+
+  Ok(())
+}
+
+
+#[test]
+fn unique_prefix() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--verb", "--col", "--fi=a.txt"],
+    MyContext::default()
+  );
+  prsr.set_prefix_matching(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert!(ctx.verbose);
+  assert!(!ctx.version);
+  assert_eq!(ctx.color, Some(true));
+  assert_eq!(ctx.file, "a.txt");
+
+  // The negated form can be abbreviated too.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--no-c"], MyContext::default());
+  prsr.set_prefix_matching(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().color, Some(false));
+
+  Ok(())
+}
+
+
+#[test]
+fn ambiguous() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--ver"], MyContext::default());
+  prsr.set_prefix_matching(true);
+  add_specs(&mut prsr)?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::Ambiguous(e)) => {
+      assert_eq!(e.opt, "--ver");
+      assert_eq!(e.candidates, vec!["--verbose", "--version"]);
+      assert_eq!(e.to_string(), "'--ver' could be --verbose, --version");
+    }
+    _ => panic!("Ambiguous option not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn not_matched() -> Result<(), Box<dyn std::error::Error>> {
+  // Hidden options must be spelled out.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--debug"], MyContext::default());
+  prsr.set_prefix_matching(true);
+  add_specs(&mut prsr)?;
+  assert!(matches!(prsr.parse(), Err(arg::ErrKind::UnknownOpt(_))));
+
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["--debug-internals"],
+    MyContext::default()
+  );
+  prsr.set_prefix_matching(true);
+  add_specs(&mut prsr)?;
+  assert!(prsr.parse().is_ok());
+
+  // Prefix matching is opt-in.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--verb"], MyContext::default());
+  add_specs(&mut prsr)?;
+  assert!(matches!(prsr.parse(), Err(arg::ErrKind::UnknownOpt(_))));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
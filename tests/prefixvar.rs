@@ -0,0 +1,223 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool,
+  color: String,
+  files: Vec<String>
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn color_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) -> Result<(), String> {
+  match args[0].as_str() {
+    "auto" | "always" | "never" => {
+      ctx.color = args[0].clone();
+      Ok(())
+    }
+    _ => Err("Invalid color".to_string())
+  }
+}
+
+fn files_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.files.extend(args.iter().cloned());
+}
+
+
+/// Add `-v`, `--color` and the files.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .build(verbose_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("color")
+      .nargs(arg::Nargs::Count(1), ["WHEN"])
+      .try_build(color_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .name("files")
+      .nargs(arg::Nargs::ZeroOrMore, ["FILE"])
+      .build(files_proc)
+  )?;
+
+  Ok(())
+}
+
+
+#[test]
+fn prepended() -> Result<(), Box<dyn std::error::Error>> {
+  // Each test uses its own variable, since tests run in parallel.
+  std::env::set_var("QARGTEST_PREFIX1", "-v --color='never'");
+
+  let mut prsr = arg::Parser::from_args_with_prefix_var(
+    "tool",
+    ["--color=always", "a b"],
+    MyContext::default(),
+    "QARGTEST_PREFIX1"
+  )?;
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  // The command line overrides the variable.
+  let ctx = prsr.into_ctx();
+  assert!(ctx.verbose);
+  assert_eq!(ctx.color, "always");
+  assert_eq!(ctx.files, vec!["a b"]);
+
+  Ok(())
+}
+
+
+#[test]
+fn unset() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args_with_prefix_var(
+    "tool",
+    ["x"],
+    MyContext::default(),
+    "QARGTEST_PREFIX2"
+  )?;
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert!(!ctx.verbose);
+  assert_eq!(ctx.files, vec!["x"]);
+
+  Ok(())
+}
+
+
+#[cfg(unix)]
+#[test]
+fn non_utf8() -> Result<(), Box<dyn std::error::Error>> {
+  use std::ffi::OsStr;
+  use std::os::unix::ffi::OsStrExt;
+
+  let val = OsStr::from_bytes(b"-v 'caf\xe9 au lait'");
+  std::env::set_var("QARGTEST_PREFIX7", val);
+
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args_with_prefix_var(
+    "tool",
+    args,
+    MyContext::default(),
+    "QARGTEST_PREFIX7"
+  )?;
+  prsr.add(arg::Builder::new().sopt('v').build(verbose_proc))?;
+  assert!(prsr.next()?.is_some());
+
+  let args = prsr.get_remaining_args_os();
+  assert_eq!(args.len(), 1);
+  assert_eq!(args[0].as_bytes(), b"caf\xe9 au lait");
+
+  Ok(())
+}
+
+
+#[test]
+fn attributed() -> Result<(), Box<dyn std::error::Error>> {
+  std::env::set_var("QARGTEST_PREFIX3", "--verbse");
+
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args_with_prefix_var(
+    "tool",
+    args,
+    MyContext::default(),
+    "QARGTEST_PREFIX3"
+  )?;
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::PrefixVar(e)) => {
+      assert_eq!(e.var, "QARGTEST_PREFIX3");
+      match e.err.as_deref() {
+        Some(arg::ErrKind::UnknownOpt(e)) => {
+          assert_eq!(e.opt, "--verbse");
+        }
+        _ => panic!("Wrapped error is missing")
+      }
+    }
+    _ => panic!("Error not attributed to variable")
+  }
+
+  std::env::set_var("QARGTEST_PREFIX4", "--color=sometimes");
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args_with_prefix_var(
+    "tool",
+    args,
+    MyContext::default(),
+    "QARGTEST_PREFIX4"
+  )?;
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::PrefixVar(e)) => {
+      assert!(matches!(e.err.as_deref(), Some(arg::ErrKind::BadArg(_))));
+      assert!(e
+        .to_string()
+        .starts_with("'QARGTEST_PREFIX4'; Bad argument"));
+    }
+    _ => panic!("Error not attributed to variable")
+  }
+
+  // Errors caused by the command line are reported as usual.
+  std::env::set_var("QARGTEST_PREFIX6", "-v");
+  let mut prsr = arg::Parser::from_args_with_prefix_var(
+    "tool",
+    ["--verbse"],
+    MyContext::default(),
+    "QARGTEST_PREFIX6"
+  )?;
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::UnknownOpt(_)) => {}
+    _ => panic!("Error attributed to variable")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn bad_quoting() {
+  std::env::set_var("QARGTEST_PREFIX5", "-v 'oops");
+
+  let args: [&str; 0] = [];
+  match arg::Parser::from_args_with_prefix_var(
+    "tool",
+    args,
+    MyContext::default(),
+    "QARGTEST_PREFIX5"
+  ) {
+    Err(arg::ErrKind::PrefixVar(e)) => {
+      assert_eq!(e.var, "QARGTEST_PREFIX5");
+      assert!(e.err.is_none());
+      assert_eq!(e.msg, "Unterminated single quote");
+    }
+    _ => panic!("Bad quoting not reported")
+  }
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
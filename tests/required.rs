@@ -0,0 +1,135 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  do_help: bool,
+  config: String,
+  user: String
+}
+
+fn help_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.do_help = true;
+}
+
+fn config_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.config = args[0].clone();
+}
+
+fn user_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.user = args[0].clone();
+}
+
+
+/// Add `--help` and two required options.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('h')
+      .lopt("help")
+      .exit(true)
+      .build(help_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("config")
+      .required(true)
+      .nargs(arg::Nargs::Count(1), ["FILE"])
+      .build(config_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('u')
+      .required(true)
+      .nargs(arg::Nargs::Count(1), ["USER"])
+      .build(user_proc)
+  )?;
+
+  Ok(())
+}
+
+
+#[test]
+fn all_present() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-u", "alice", "--config", "a.conf"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.config, "a.conf");
+  assert_eq!(ctx.user, "alice");
+
+  Ok(())
+}
+
+
+#[test]
+fn missing_all() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::MissOpt(e)) => {
+      assert_eq!(e.opts, vec!["<--config FILE>", "<-u USER>"]);
+      assert_eq!(e.specs.len(), 2);
+    }
+    _ => panic!("Missing required options not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn missing_one() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--config=a.conf"], MyContext::default());
+  add_specs(&mut prsr)?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::MissOpt(e)) => {
+      assert_eq!(e.opts, vec!["<-u USER>"]);
+      assert_eq!(e.to_string(), "<-u USER>");
+    }
+    _ => panic!("Missing required option not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn exit_skips_validation() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--help"], MyContext::default());
+  add_specs(&mut prsr)?;
+
+  assert!(prsr.parse()?.is_some());
+  assert!(prsr.get_ctx().do_help);
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
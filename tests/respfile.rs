@@ -0,0 +1,274 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool,
+  define: Vec<String>,
+  files: Vec<String>
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+fn define_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.define.push(args[0].clone());
+}
+
+fn files_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.files.extend(args.iter().cloned());
+}
+
+
+/// Add `-v`, `-D` and the files.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .build(verbose_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('D')
+      .nargs(arg::Nargs::Count(1), ["DEF"])
+      .build(define_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .name("files")
+      .nargs(arg::Nargs::ZeroOrMore, ["FILE"])
+      .build(files_proc)
+  )?;
+
+  Ok(())
+}
+
+
+/// Create an empty directory for a test's response files.
+fn mkdir(test: &str) -> std::io::Result<PathBuf> {
+  let dir = std::env::temp_dir().join(format!(
+    "qargtest-{}-{}",
+    std::process::id(),
+    test
+  ));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir)?;
+  Ok(dir)
+}
+
+fn at(path: &Path) -> String {
+  format!("@{}", path.display())
+}
+
+
+#[test]
+fn expanded() -> Result<(), Box<dyn std::error::Error>> {
+  let dir = mkdir("expanded")?;
+  let inner = dir.join("inner.rsp");
+  let outer = dir.join("outer.rsp");
+  fs::write(&inner, "-D 'X=a b'\n")?;
+  fs::write(
+    &outer,
+    format!(
+      "-v {}\n\"two words\" @@literal escaped\\ space\n",
+      at(&inner)
+    )
+  )?;
+
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    [&at(&outer), "last"],
+    MyContext::default()
+  );
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  let m = prsr.get_matches().indices("files").to_vec();
+  let ctx = prsr.into_ctx();
+  assert!(ctx.verbose);
+  assert_eq!(ctx.define, vec!["X=a b"]);
+  assert_eq!(
+    ctx.files,
+    vec!["two words", "@literal", "escaped space", "last"]
+  );
+
+  // All arguments from a response file map to the "@file" argument.
+  assert_eq!(m, [0]);
+
+  fs::remove_dir_all(&dir)?;
+
+  Ok(())
+}
+
+
+#[test]
+fn disabled_and_escaped() -> Result<(), Box<dyn std::error::Error>> {
+  let args = ["-D", "@@x", "--", "@nonexistent", "@@y"];
+
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.define, vec!["@x"]);
+  assert_eq!(ctx.files, vec!["@nonexistent", "@@y"]);
+
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.define, vec!["@@x"]);
+  assert_eq!(ctx.files, vec!["@nonexistent", "@@y"]);
+
+  Ok(())
+}
+
+
+#[test]
+fn cycle() -> Result<(), Box<dyn std::error::Error>> {
+  let dir = mkdir("cycle")?;
+  let a = dir.join("a.rsp");
+  let b = dir.join("b.rsp");
+  fs::write(&a, format!("-v {}", at(&b)))?;
+  fs::write(&b, at(&a))?;
+
+  let mut prsr =
+    arg::Parser::from_args("tool", [&at(&a)], MyContext::default());
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::RespFile(e)) => {
+      assert_eq!(e.path, a);
+      assert_eq!(e.msg, "Response file includes itself");
+    }
+    _ => panic!("Response file cycle not reported")
+  }
+  assert!(!prsr.get_ctx().verbose);
+
+  // Including the same file twice is fine, as long as it's not nested.
+  fs::write(&b, "x")?;
+  fs::write(&a, format!("{} {}", at(&b), at(&b)))?;
+  let mut prsr =
+    arg::Parser::from_args("tool", [&at(&a)], MyContext::default());
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.into_ctx().files, vec!["x", "x"]);
+
+  fs::remove_dir_all(&dir)?;
+
+  Ok(())
+}
+
+
+#[test]
+fn relative() -> Result<(), Box<dyn std::error::Error>> {
+  let dir = mkdir("relative")?;
+  fs::create_dir(dir.join("sub"))?;
+  fs::write(dir.join("outer.rsp"), "@sub/inner.rsp")?;
+  fs::write(dir.join("sub").join("inner.rsp"), "-v @last.rsp")?;
+  fs::write(dir.join("sub").join("last.rsp"), "x")?;
+
+  // Nested paths are relative to the including file, not the current
+  // directory.
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    [&at(&dir.join("outer.rsp"))],
+    MyContext::default()
+  );
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  let ctx = prsr.into_ctx();
+  assert!(ctx.verbose);
+  assert_eq!(ctx.files, vec!["x"]);
+
+  fs::remove_dir_all(&dir)?;
+
+  Ok(())
+}
+
+
+#[cfg(unix)]
+#[test]
+fn non_utf8() -> Result<(), Box<dyn std::error::Error>> {
+  use std::os::unix::ffi::OsStrExt;
+
+  let dir = mkdir("non_utf8")?;
+  let rsp = dir.join("args.rsp");
+  fs::write(&rsp, b"-v 'caf\xe9 au lait'\n")?;
+
+  // The argument is passed on as it is, rather than failing to read the
+  // file.
+  let mut prsr =
+    arg::Parser::from_args("tool", [&at(&rsp)], MyContext::default());
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  assert!(prsr.next()?.is_some());
+  assert!(prsr.get_ctx().verbose);
+  let args = prsr.get_remaining_args_os();
+  assert_eq!(args.len(), 1);
+  assert_eq!(args[0].as_bytes(), b"caf\xe9 au lait");
+
+  fs::remove_dir_all(&dir)?;
+
+  Ok(())
+}
+
+
+#[test]
+fn errors() -> Result<(), Box<dyn std::error::Error>> {
+  let dir = mkdir("errors")?;
+  let missing = dir.join("missing.rsp");
+  let quote = dir.join("quote.rsp");
+  fs::write(&quote, "-D 'unterminated")?;
+
+  let mut prsr =
+    arg::Parser::from_args("tool", [&at(&missing)], MyContext::default());
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::RespFile(e)) => assert_eq!(e.path, missing),
+    _ => panic!("Missing response file not reported")
+  }
+
+  let mut prsr =
+    arg::Parser::from_args("tool", [&at(&quote)], MyContext::default());
+  prsr.set_response_files(true);
+  add_specs(&mut prsr)?;
+  match prsr.parse() {
+    Err(arg::ErrKind::RespFile(e)) => {
+      assert_eq!(e.path, quote);
+      assert_eq!(e.msg, "Unterminated single quote");
+    }
+    _ => panic!("Bad quoting not reported")
+  }
+
+  fs::remove_dir_all(&dir)?;
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
@@ -0,0 +1,279 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbosity: u8,
+  do_help: bool,
+  force: bool,
+  src: String,
+  name: String
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbosity += 1;
+}
+
+fn help_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.do_help = true;
+}
+
+fn force_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.force = true;
+}
+
+fn src_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.src = args[0].clone();
+}
+
+fn name_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) {
+  ctx.name = args[0].clone();
+}
+
+
+/// Add the global `-v`, `-h` and the `clone` and `init` sub-commands.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .global(true)
+      .build(verbose_proc)
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .sopt('h')
+      .lopt("help")
+      .exit(true)
+      .build(help_proc)
+  )?;
+
+  let mut clone = arg::Parser::new_subcmd();
+  clone.add(
+    arg::Builder::new()
+      .sopt('f')
+      .lopt("force")
+      .build(force_proc)
+  )?;
+  clone.add(
+    arg::Builder::new()
+      .name("src")
+      .required(true)
+      .nargs(arg::Nargs::Count(1), ["SRC"])
+      .build(src_proc)
+  )?;
+  clone.add(
+    arg::Builder::new()
+      .sopt('h')
+      .lopt("help")
+      .exit(true)
+      .build(help_proc)
+  )?;
+  prsr.add_subcmd("clone", ["Clone a repository."], clone)?;
+
+  let mut init = arg::Parser::new_subcmd();
+  init.add(
+    arg::Builder::new()
+      .name("name")
+      .nargs(arg::Nargs::Count(1), ["NAME"])
+      .build(name_proc)
+  )?;
+  prsr.add_subcmd("init", ["Create an empty repository."], init)?;
+
+  Ok(())
+}
+
+
+#[test]
+fn dispatch() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-v", "clone", "--force", "-v", "foo"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+
+  assert!(prsr.parse()?.is_none());
+  assert_eq!(prsr.get_subcmd(), Some("clone"));
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.verbosity, 2);
+  assert!(ctx.force);
+  assert_eq!(ctx.src, "foo");
+
+  let mut prsr =
+    arg::Parser::from_args("tool", ["init", "bar"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_subcmd(), Some("init"));
+  assert_eq!(prsr.get_ctx().name, "bar");
+
+  Ok(())
+}
+
+#[test]
+fn global_matches() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["-v", "clone", "--verbose", "foo"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  // Global options are recorded by the parser which defines them, as well as
+  // by the sub-command's parser.
+  assert_eq!(prsr.get_matches().count("verbose"), 2);
+  assert_eq!(prsr.get_matches().indices("verbose"), [0, 2]);
+  let sub = prsr.get_subcmd_parser().unwrap();
+  assert_eq!(sub.get_matches().count("verbose"), 1);
+  assert_eq!(sub.get_matches().value("src").unwrap(), "foo");
+  assert!(!prsr.get_matches().contains("src"));
+
+  Ok(())
+}
+
+#[test]
+fn subcmd_posplit() -> Result<(), Box<dyn std::error::Error>> {
+  // Arguments after "--" are not options, even in the sub-command.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--", "init", "-v"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_subcmd(), Some("init"));
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.verbosity, 0);
+  assert_eq!(ctx.name, "-v");
+
+  Ok(())
+}
+
+#[test]
+fn no_subcmd() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args("tool", ["-v"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_subcmd(), None);
+  assert_eq!(prsr.get_ctx().verbosity, 1);
+
+  Ok(())
+}
+
+#[test]
+fn subcmd_exit() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["clone", "--help"], MyContext::default());
+  add_specs(&mut prsr)?;
+
+  let spec = prsr.parse()?;
+  assert!(spec.is_some());
+  assert!(prsr.get_ctx().do_help);
+
+  let sub = prsr.get_subcmd_parser().unwrap();
+  assert_eq!(sub.get_arg0(), "tool clone");
+
+  // The sub-command's help lists the global options it inherited.
+  let mut buf = Vec::new();
+  sub.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+  assert!(help
+    .starts_with("Usage: tool clone [--verbose] [--force] <SRC> [--help]\n"));
+  assert!(help.contains("  -v, --verbose\n"));
+
+  Ok(())
+}
+
+#[test]
+fn nested_subcmd() -> Result<(), Box<dyn std::error::Error>> {
+  let mut child = arg::Parser::new_subcmd();
+  child.add(arg::Builder::new().sopt('f').build(force_proc))?;
+  let mut parent = arg::Parser::new_subcmd();
+  parent.add_subcmd("child", ["The child."], child)?;
+
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    ["parent", "child", "-f"],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.add_subcmd("parent", ["The parent."], parent)?;
+  prsr.parse()?;
+  assert!(prsr.get_ctx().force);
+
+  // Sub-commands registered before their parent still get the full name.
+  let parent = prsr.get_subcmd_parser().unwrap();
+  let child = parent.get_subcmd_parser().unwrap();
+  assert_eq!(parent.get_arg0(), "tool parent");
+  assert_eq!(child.get_arg0(), "tool parent child");
+
+  Ok(())
+}
+
+#[test]
+fn unknown_subcmd() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["frobnicate"], MyContext::default());
+  add_specs(&mut prsr)?;
+  match prsr.next() {
+    Err(arg::ErrKind::UnknownCmd(_)) => {}
+    _ => panic!("Expected an unknown command error")
+  }
+
+  Ok(())
+}
+
+#[test]
+fn subcmd_collision() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  let res = prsr.add_subcmd("init", ["Again."], arg::Parser::new_subcmd());
+  match res {
+    Err(arg::ErrKind::Collision(_)) => {}
+    _ => panic!("Expected a collision error")
+  }
+
+  Ok(())
+}
+
+#[test]
+fn subcmd_usage() -> Result<(), Box<dyn std::error::Error>> {
+  let args: [&str; 0] = [];
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  let mut buf = Vec::new();
+  prsr.usage(&mut buf);
+  let help = String::from_utf8(buf)?;
+  assert!(help.contains("<COMMAND>"));
+  assert!(help.contains("commands:"));
+  assert!(help.contains("Clone a repository."));
+
+  Ok(())
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
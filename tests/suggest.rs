@@ -0,0 +1,98 @@
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  verbose: bool
+}
+
+fn verbose_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  _args: &Vec<String>
+) {
+  ctx.verbose = true;
+}
+
+
+/// Add two similar long options and a hidden one.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('v')
+      .lopt("verbose")
+      .build(verbose_proc)
+  )?;
+  prsr.add(arg::Builder::new().lopt("version").build(verbose_proc))?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("secret")
+      .hidden(true)
+      .build(verbose_proc)
+  )?;
+
+  Ok(())
+}
+
+fn suggestion(
+  args: &[&str]
+) -> Result<Option<String>, arg::ErrKind<MyContext>> {
+  let mut prsr = arg::Parser::from_args("tool", args, MyContext::default());
+  add_specs(&mut prsr)?;
+  match prsr.next() {
+    Err(arg::ErrKind::UnknownOpt(e)) => Ok(e.suggestion),
+    _ => panic!("Expected an unknown option error")
+  }
+}
+
+
+#[test]
+fn lopt_typo() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--verbse"], MyContext::default());
+  add_specs(&mut prsr)?;
+  match prsr.next() {
+    Err(arg::ErrKind::UnknownOpt(e)) => {
+      assert_eq!(e.opt, "--verbse");
+      assert_eq!(e.suggestion.as_deref(), Some("--verbose"));
+      assert_eq!(
+        e.to_string(),
+        "Unknown long option 'verbse'; did you mean --verbose?"
+      );
+    }
+    _ => panic!("Expected an unknown option error")
+  }
+
+  assert_eq!(suggestion(&["--verison"])?.as_deref(), Some("--version"));
+  assert_eq!(suggestion(&["--verbsoe=1"])?.as_deref(), Some("--verbose"));
+
+  Ok(())
+}
+
+
+#[test]
+fn lopt_no_match() -> Result<(), Box<dyn std::error::Error>> {
+  assert_eq!(suggestion(&["--frobnicate"])?, None);
+
+  Ok(())
+}
+
+
+#[test]
+fn hidden_not_suggested() -> Result<(), Box<dyn std::error::Error>> {
+  assert_eq!(suggestion(&["--secert"])?, None);
+
+  Ok(())
+}
+
+
+#[test]
+fn sopt_case() -> Result<(), Box<dyn std::error::Error>> {
+  assert_eq!(suggestion(&["-V"])?.as_deref(), Some("-v"));
+  assert_eq!(suggestion(&["--v"])?.as_deref(), Some("-v"));
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */
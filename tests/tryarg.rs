@@ -0,0 +1,94 @@
+// Handlers must match `arg::Handler`, which takes a `&Vec<String>`.
+#![allow(clippy::ptr_arg)]
+
+use std::ffi::OsString;
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  port: u16,
+  level: u8
+}
+
+fn port_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &Vec<String>
+) -> Result<(), String> {
+  ctx.port = args[0].parse().map_err(|e| format!("{}", e))?;
+  Ok(())
+}
+
+fn level_proc(
+  _spec: &arg::Spec<MyContext>,
+  ctx: &mut MyContext,
+  args: &[OsString]
+) -> Result<(), String> {
+  match args[0].to_str() {
+    Some("low") => ctx.level = 1,
+    Some("high") => ctx.level = 2,
+    _ => return Err("Expected 'low' or 'high'.".to_string())
+  }
+  Ok(())
+}
+
+fn mkport() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .sopt('p')
+    .lopt("port")
+    .nargs(arg::Nargs::Count(1), ["PORT"])
+    .try_build(port_proc)
+}
+
+fn mklevel() -> arg::Spec<MyContext> {
+  arg::Builder::new()
+    .lopt("level")
+    .nargs(arg::Nargs::Count(1), ["LEVEL"])
+    .try_build_os(level_proc)
+}
+
+#[test]
+fn accepted() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tryarg",
+    ["--port", "8080", "--level", "high"],
+    MyContext::default()
+  );
+  prsr.add(mkport())?;
+  prsr.add(mklevel())?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().port, 8080);
+  assert_eq!(prsr.get_ctx().level, 2);
+  Ok(())
+}
+
+#[test]
+fn rejected() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tryarg", ["-p", "abc"], MyContext::default());
+  prsr.add(mkport())?;
+  match prsr.next() {
+    Err(arg::ErrKind::BadArg(err)) => {
+      let usage = err.spec.borrow().get_opts_usage_str();
+      assert_eq!(usage, "-p PORT, --port PORT");
+      assert_eq!(err.args, vec![OsString::from("abc")]);
+      assert_eq!(err.msg, "invalid digit found in string");
+    }
+    _ => panic!("Expected a bad argument error")
+  }
+
+  let mut prsr =
+    arg::Parser::from_args("tryarg", ["--level=medium"], MyContext::default());
+  prsr.add(mklevel())?;
+  match prsr.next() {
+    Err(arg::ErrKind::BadArg(err)) => {
+      assert_eq!(err.args, vec![OsString::from("medium")]);
+    }
+    _ => panic!("Expected a bad argument error")
+  }
+
+  Ok(())
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+use qargparser as arg;
+
+#[derive(Default)]
+struct MyContext {
+  port: u16,
+  mapping: Option<(String, PathBuf)>,
+  ids: Vec<u32>,
+  level: Option<u8>
+}
+
+
+/// Add typed options and positional arguments of various shapes.
+fn add_specs(
+  prsr: &mut arg::Parser<MyContext>
+) -> Result<(), arg::ErrKind<MyContext>> {
+  prsr.add(
+    arg::Builder::new()
+      .sopt('p')
+      .lopt("port")
+      .nargs(arg::Nargs::Count(1), ["PORT"])
+      .build_typed(|_spec, ctx: &mut MyContext, port: u16| {
+        if port == 0 {
+          return Err("Port must not be 0".to_string());
+        }
+        ctx.port = port;
+        Ok(())
+      })
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("map")
+      .nargs(arg::Nargs::Count(2), ["NAME", "PATH"])
+      .build_typed(|_spec, ctx: &mut MyContext, m: (String, PathBuf)| {
+        ctx.mapping = Some(m);
+        Ok(())
+      })
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .lopt("level")
+      .nargs(arg::Nargs::Optional, ["LEVEL"])
+      .build_typed(|_spec, ctx: &mut MyContext, level: Option<u8>| {
+        ctx.level = level.or(Some(1));
+        Ok(())
+      })
+  )?;
+  prsr.add(
+    arg::Builder::new()
+      .name("ids")
+      .nargs(arg::Nargs::ZeroOrMore, ["ID"])
+      .build_typed(|_spec, ctx: &mut MyContext, ids: Vec<u32>| {
+        ctx.ids = ids;
+        Ok(())
+      })
+  )?;
+
+  Ok(())
+}
+
+
+#[test]
+fn converted() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr = arg::Parser::from_args(
+    "tool",
+    [
+      "-p",
+      "8080",
+      "--map",
+      "home",
+      "/home",
+      "--level=3",
+      "1",
+      "2"
+    ],
+    MyContext::default()
+  );
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+
+  let ctx = prsr.into_ctx();
+  assert_eq!(ctx.port, 8080);
+  assert_eq!(
+    ctx.mapping,
+    Some((String::from("home"), PathBuf::from("/home")))
+  );
+  assert_eq!(ctx.ids, vec![1, 2]);
+  assert_eq!(ctx.level, Some(3));
+
+  // An optional argument which isn't passed converts to None.
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--level"], MyContext::default());
+  add_specs(&mut prsr)?;
+  prsr.parse()?;
+  assert_eq!(prsr.get_ctx().level, Some(1));
+
+  Ok(())
+}
+
+
+#[test]
+fn conversion_error() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--port", "http"], MyContext::default());
+  add_specs(&mut prsr)?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::Conv(e)) => {
+      assert!(e.spec.borrow().get_usage_str().contains("--port"));
+      assert_eq!(e.arg, Some("http".into()));
+      assert_eq!(e.index, 0);
+      assert_eq!(e.to_string(), "'http'; invalid digit found in string");
+    }
+    _ => panic!("Conversion error not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn conversion_error_index() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["1", "2", "x"], MyContext::default());
+  add_specs(&mut prsr)?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::Conv(e)) => {
+      assert_eq!(e.arg, Some("x".into()));
+      assert_eq!(e.index, 2);
+    }
+    _ => panic!("Conversion error not reported")
+  }
+
+  Ok(())
+}
+
+
+#[test]
+fn handler_error() -> Result<(), Box<dyn std::error::Error>> {
+  let mut prsr =
+    arg::Parser::from_args("tool", ["--port", "0"], MyContext::default());
+  add_specs(&mut prsr)?;
+
+  match prsr.parse() {
+    Err(arg::ErrKind::BadArg(e)) => {
+      assert_eq!(e.args, vec!["0"]);
+      assert_eq!(e.msg, "Port must not be 0");
+    }
+    _ => panic!("Handler error not reported")
+  }
+
+  Ok(())
+}
+
+/* vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 : */